@@ -0,0 +1,129 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{eyre, Context, Result};
+
+use crate::settings::data_path;
+
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+/// Named bookmarks, marks are bookmarks with a single character name
+#[derive(Default)]
+pub struct Bookmarks {
+    entries: BTreeMap<String, PathBuf>,
+    file: Option<PathBuf>,
+}
+
+impl Bookmarks {
+    pub fn load() -> Result<Self> {
+        Self::load_from(data_path(BOOKMARKS_FILE))
+    }
+
+    fn load_from(file: Option<PathBuf>) -> Result<Self> {
+        let entries = match &file {
+            Some(file) if file.exists() => {
+                let content = fs::read_to_string(file).wrap_err_with(|| {
+                    format!("Could not read bookmarks: \"{}\"", file.display())
+                })?;
+                serde_json::from_str(&content).wrap_err_with(|| {
+                    format!("Could not parse bookmarks: \"{}\"", file.display())
+                })?
+            }
+            _ => BTreeMap::default(),
+        };
+        Ok(Self { entries, file })
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(file, serde_json::to_string_pretty(&self.entries)?)
+            .wrap_err_with(|| format!("Could not save bookmarks: \"{}\"", file.display()))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PathBuf> {
+        self.entries.get(name)
+    }
+
+    pub fn get_index(&self, i: usize) -> Option<(&String, &PathBuf)> {
+        self.entries.iter().nth(i)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &PathBuf)> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn set(&mut self, name: &str, path: &Path) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(eyre!("Bookmark names can not be empty"));
+        }
+        self.entries.insert(name.to_string(), path.to_path_buf());
+        self.save()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<()> {
+        self.entries.remove(name);
+        self.save()
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<()> {
+        // checked before anything is removed, so the bookmark is never lost
+        let to = to.trim();
+        if to.is_empty() {
+            return Err(eyre!("Bookmark names can not be empty"));
+        }
+        if !self.entries.contains_key(from) {
+            return Err(eyre!("No bookmark named \"{from}\""));
+        }
+        if from == to {
+            return Ok(());
+        }
+        if self.entries.contains_key(to) {
+            return Err(eyre!("Bookmark \"{to}\" already exists"));
+        }
+        let path = self.entries.remove(from).expect("checked above");
+        self.set(to, &path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bookmark_tests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("bookmarks.json");
+
+        let mut bookmarks = Bookmarks::load_from(Some(file.clone())).unwrap();
+        bookmarks.set("a", Path::new("/tmp")).unwrap();
+        bookmarks.set("work", Path::new("/home")).unwrap();
+        bookmarks.rename("work", "projects").unwrap();
+        assert!(bookmarks.rename("a", "projects").is_err());
+        assert!(bookmarks.rename("a", "  ").is_err());
+        assert_eq!(bookmarks.get("a"), Some(&PathBuf::from("/tmp")));
+        bookmarks.rename("a", "a").unwrap();
+        assert!(bookmarks.set(" ", Path::new("/")).is_err());
+
+        let mut bookmarks = Bookmarks::load_from(Some(file.clone())).unwrap();
+        assert_eq!(bookmarks.get("a"), Some(&PathBuf::from("/tmp")));
+        assert_eq!(bookmarks.get("projects"), Some(&PathBuf::from("/home")));
+        assert_eq!(bookmarks.get("work"), None);
+
+        bookmarks.remove("a").unwrap();
+        let bookmarks = Bookmarks::load_from(Some(file.clone())).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+    }
+}
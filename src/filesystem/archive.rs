@@ -0,0 +1,426 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Read,
+    path::{Component, Path, PathBuf},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Timelike};
+use eyre::{eyre, Context, Result};
+
+use super::read::{File, FileKind};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ArchiveKind {
+    Tar,
+    TarGz,
+    TarZst,
+    Zip,
+}
+
+const EXTENSIONS: [(&str, ArchiveKind); 6] = [
+    (".tar.gz", ArchiveKind::TarGz),
+    (".tgz", ArchiveKind::TarGz),
+    (".tar.zst", ArchiveKind::TarZst),
+    (".tzst", ArchiveKind::TarZst),
+    (".tar", ArchiveKind::Tar),
+    (".zip", ArchiveKind::Zip),
+];
+
+impl ArchiveKind {
+    /// Picks the format by the file name, e.g. `.tar.gz` or `.tgz`
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        EXTENSIONS
+            .into_iter()
+            .find(|(ext, _)| name.ends_with(ext))
+            .map(|(_, kind)| kind)
+    }
+
+    /// The name of an archive without its extension, e.g. `src` for `src.tar.gz`
+    pub fn stem(path: &Path) -> Option<String> {
+        let name = path.file_name()?.to_string_lossy().to_string();
+        let lower = name.to_lowercase();
+        let (ext, _) = EXTENSIONS.iter().find(|(ext, _)| lower.ends_with(ext))?;
+        Some(name[..name.len() - ext.len()].to_string())
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Tar => "tar",
+            Self::TarGz => "tar.gz",
+            Self::TarZst => "tar.zst",
+            Self::Zip => "zip",
+        }
+    }
+}
+
+/// An entry of an archive, `path` is relative to its root
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member {
+    pub path: PathBuf,
+    pub kind: FileKind,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub mode: Option<u32>,
+    // where a symlink points to
+    pub link: Option<PathBuf>,
+}
+
+impl Member {
+    fn dir(path: PathBuf) -> Self {
+        Self {
+            path,
+            kind: FileKind::Dir,
+            size: 0,
+            modified: None,
+            mode: None,
+            link: None,
+        }
+    }
+}
+
+/// Every entry of the archive at `path`, sorted by path. Directories that only show up in the
+/// paths of other entries are added
+pub fn members(path: &Path) -> Result<Vec<Member>> {
+    let kind =
+        ArchiveKind::detect(path).ok_or_else(|| eyre!("Not an archive: \"{}\"", path.display()))?;
+    let file = fs::File::open(path)
+        .wrap_err_with(|| format!("Could not open archive: \"{}\"", path.display()))?;
+    let list = match kind {
+        ArchiveKind::Tar => tar_members(file),
+        ArchiveKind::TarGz => tar_members(flate2::read::GzDecoder::new(file)),
+        ArchiveKind::TarZst => tar_members(zstd::Decoder::new(file)?),
+        ArchiveKind::Zip => zip_members(file),
+    }
+    .wrap_err_with(|| format!("Could not read archive: \"{}\"", path.display()))?;
+
+    // later entries replace earlier ones, like they would when extracting
+    let mut members = BTreeMap::new();
+    for member in list {
+        for dir in member.path.ancestors().skip(1) {
+            if !dir.as_os_str().is_empty() && !members.contains_key(dir) {
+                members.insert(dir.to_path_buf(), Member::dir(dir.to_path_buf()));
+            }
+        }
+        members.insert(member.path.clone(), member);
+    }
+    Ok(members.into_values().collect())
+}
+
+/// Makes an entry path relative, entries leaving the archive with `..` are skipped
+pub fn clean(path: &Path) -> Option<PathBuf> {
+    let mut clean = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(c) => clean.push(c),
+            Component::ParentDir => return None,
+            _ => {}
+        }
+    }
+    (!clean.as_os_str().is_empty()).then_some(clean)
+}
+
+fn tar_members(reader: impl Read) -> Result<Vec<Member>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut members = vec![];
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let Some(path) = clean(&entry.path()?) else {
+            continue;
+        };
+        let header = entry.header();
+        let entry_type = header.entry_type();
+        let kind = if entry_type.is_dir() {
+            FileKind::Dir
+        } else if entry_type.is_symlink() {
+            FileKind::Symlink
+        } else if entry_type.is_file() || entry_type.is_hard_link() {
+            FileKind::File
+        } else {
+            FileKind::Other
+        };
+        members.push(Member {
+            path,
+            kind,
+            size: entry.size(),
+            modified: header
+                .mtime()
+                .ok()
+                .map(|s| UNIX_EPOCH + Duration::from_secs(s)),
+            mode: header.mode().ok().map(|m| m & 0o7777),
+            link: entry.link_name().ok().flatten().map(|l| l.into_owned()),
+        });
+    }
+    Ok(members)
+}
+
+fn zip_members(file: fs::File) -> Result<Vec<Member>> {
+    let mut archive = zip::ZipArchive::new(file)?;
+    let mut members = vec![];
+    for i in 0..archive.len() {
+        let entry = archive.by_index_raw(i)?;
+        let Some(path) = entry.enclosed_name().as_deref().and_then(clean) else {
+            continue;
+        };
+        let kind = if entry.is_dir() {
+            FileKind::Dir
+        } else if entry.is_symlink() {
+            FileKind::Symlink
+        } else {
+            FileKind::File
+        };
+        let modified = entry.last_modified().and_then(from_zip_time);
+        members.push(Member {
+            path,
+            kind,
+            size: entry.size(),
+            modified,
+            mode: entry.unix_mode().map(|m| m & 0o7777),
+            link: None,
+        });
+    }
+    Ok(members)
+}
+
+// zip stores the local time without a timezone
+fn from_zip_time(t: zip::DateTime) -> Option<SystemTime> {
+    let date = NaiveDate::from_ymd_opt(t.year().into(), t.month().into(), t.day().into())?
+        .and_hms_opt(t.hour().into(), t.minute().into(), t.second().into())?;
+    Local.from_local_datetime(&date).earliest().map(Into::into)
+}
+
+pub fn zip_time(time: SystemTime) -> Option<zip::DateTime> {
+    let t = DateTime::<Local>::from(time);
+    zip::DateTime::from_date_and_time(
+        t.year().try_into().ok()?,
+        t.month() as u8,
+        t.day() as u8,
+        t.hour() as u8,
+        t.minute() as u8,
+        t.second() as u8,
+    )
+    .ok()
+}
+
+/// Splits a path inside an archive into the archive file and the path within it
+pub fn split(path: &Path) -> Option<(&Path, &Path)> {
+    let archive = path
+        .ancestors()
+        .find(|a| ArchiveKind::detect(a).is_some() && a.is_file())?;
+    Some((archive, path.strip_prefix(archive).ok()?))
+}
+
+/// The listing of an archive that is being browsed
+pub struct Archive {
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+    pub members: Vec<Member>,
+}
+
+impl Archive {
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            modified: fs::metadata(path)?.modified().ok(),
+            members: members(path)?,
+        })
+    }
+
+    /// The members directly inside `dir`, an empty path being the root of the archive
+    pub fn children<'a>(&'a self, dir: &'a Path) -> impl Iterator<Item = &'a Member> {
+        self.members
+            .iter()
+            .filter(move |m| m.path.parent() == Some(dir))
+    }
+
+    /// The members inside `dir` as files, their paths go through the archive
+    pub fn files(&self, dir: &Path) -> Result<Vec<File>> {
+        let is_dir = dir.as_os_str().is_empty()
+            || self
+                .members
+                .iter()
+                .any(|m| m.path == dir && m.kind == FileKind::Dir);
+        if !is_dir {
+            return Err(eyre!(
+                "Could not read path: \"{}\".",
+                self.path.join(dir).display()
+            ));
+        }
+        Ok(self
+            .children(dir)
+            .map(|m| File {
+                path: self.path.join(&m.path),
+                // links can not be followed inside an archive
+                kind: match m.kind {
+                    FileKind::Symlink => FileKind::Other,
+                    kind => kind,
+                },
+                name: m.path.file_name().unwrap_or_default().to_os_string(),
+                size: m.size,
+                modified: m.modified,
+                mode: m.mode,
+                readonly: true,
+            })
+            .collect())
+    }
+}
+
+/// An archive being opened in the background, so a large one does not hold up the ui
+pub struct Opening {
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+    receiver: Receiver<Result<Archive>>,
+}
+
+impl Opening {
+    fn start(path: &Path, modified: Option<SystemTime>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let task_path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            let _ = sender.send(Archive::open(&task_path));
+        });
+        Self {
+            path: path.to_path_buf(),
+            modified,
+            receiver,
+        }
+    }
+}
+
+/// Lists `path` like `read_path`, but also inside archives. `cache` keeps the last archive read
+/// so it is only read again once it changes, `opening` is the one being read meanwhile. `None`
+/// means the archive is not read yet
+pub async fn read_path(
+    path: &Path,
+    cache: &mut Option<Archive>,
+    opening: &mut Option<Opening>,
+) -> Result<Option<Vec<File>>> {
+    let Some((archive, inner)) = split(path) else {
+        return super::read::read_path(&path.to_path_buf()).await.map(Some);
+    };
+    let modified = fs::metadata(archive)?.modified().ok();
+    if !cache
+        .as_ref()
+        .is_some_and(|c| c.path == archive && c.modified == modified)
+    {
+        if !opening
+            .as_ref()
+            .is_some_and(|o| o.path == archive && o.modified == modified)
+        {
+            *opening = Some(Opening::start(archive, modified));
+        }
+        let receiver = &opening.as_ref().expect("was just started").receiver;
+        let opened = match receiver.try_recv() {
+            Ok(opened) => opened,
+            Err(TryRecvError::Empty) => return Ok(None),
+            Err(TryRecvError::Disconnected) => {
+                Err(eyre!("Could not open archive: \"{}\"", archive.display()))
+            }
+        };
+        *opening = None;
+        *cache = Some(opened?);
+    }
+    cache
+        .as_ref()
+        .expect("was just read")
+        .files(inner)
+        .map(Some)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn detect_tests() {
+        let names = [
+            ("a.tar", Some(ArchiveKind::Tar)),
+            ("a.TGZ", Some(ArchiveKind::TarGz)),
+            ("a.tar.gz", Some(ArchiveKind::TarGz)),
+            ("a.tar.zst", Some(ArchiveKind::TarZst)),
+            ("a.zip", Some(ArchiveKind::Zip)),
+            ("a.gz", None),
+            ("tar", None),
+        ];
+        for (name, expected) in names {
+            assert_eq!(expected, ArchiveKind::detect(Path::new(name)));
+        }
+        assert_eq!(
+            ArchiveKind::stem(Path::new("src.Tar.Gz")).as_deref(),
+            Some("src")
+        );
+        assert_eq!(clean(Path::new("./a/b")), Some(PathBuf::from("a/b")));
+        assert_eq!(clean(Path::new("/a/../b")), None);
+    }
+
+    #[tokio::test]
+    async fn members_tests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+
+        let path = dir.join("test.tar.gz");
+        let gz = flate2::write::GzEncoder::new(
+            fs::File::create(&path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut tar = tar::Builder::new(gz);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        header.set_mtime(60);
+        header.set_cksum();
+        tar.append_data(&mut header, "src/deep/a.txt", &b"hello"[..])
+            .unwrap();
+        tar.into_inner().unwrap().finish().unwrap();
+
+        let members = members(&path).unwrap();
+        let paths = members
+            .iter()
+            .map(|m| m.path.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["src", "src/deep", "src/deep/a.txt"]);
+        assert_eq!(members[2].size, 5);
+        assert_eq!(members[2].mode, Some(0o644));
+        assert_eq!(
+            members[2].modified,
+            Some(UNIX_EPOCH + Duration::from_secs(60))
+        );
+
+        let path = dir.join("test.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        zip.start_file("b.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"hi").unwrap();
+        zip.add_directory("empty", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.finish().unwrap();
+
+        let (mut cache, mut opening) = (None, None);
+        let files = loop {
+            match read_path(&path, &mut cache, &mut opening).await.unwrap() {
+                Some(files) => break files,
+                None => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        assert!(opening.is_none());
+        let names = files
+            .iter()
+            .map(|f| f.name.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["b.txt", "empty"]);
+        assert_eq!(files[1].kind, FileKind::Dir);
+        assert_eq!(files[0].path, path.join("b.txt"));
+        assert!(read_path(&path.join("empty"), &mut cache, &mut opening)
+            .await
+            .unwrap()
+            .is_some_and(|files| files.is_empty()));
+        assert!(read_path(&path.join("b.txt"), &mut cache, &mut opening)
+            .await
+            .is_err());
+    }
+}
@@ -1,3 +1,8 @@
+pub mod archive;
 pub mod modify;
+pub mod path;
+pub mod preview;
+pub mod previewer;
 pub mod read;
+pub mod sort;
 pub mod utils;
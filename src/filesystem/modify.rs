@@ -1,9 +1,16 @@
-use std::path::{Path, PathBuf};
+use std::{
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
 
-use eyre::Result;
+use eyre::{eyre, Context, Result};
 use tokio::fs;
 
-use super::utils;
+use super::{
+    archive::{clean, zip_time, ArchiveKind},
+    utils::{self, get_mode},
+};
 
 pub async fn create_file(file: &str, current_path: &Path) -> Result<()> {
     match utils::get_type_by_name(file) {
@@ -33,3 +40,413 @@ pub async fn delete_file(file: &Path) -> Result<()> {
     }
     Ok(())
 }
+
+/// Where `file` ends up when copied or moved to `dest`, existing directories get the file put
+/// inside of them
+fn get_destination(file: &Path, dest: &Path, multiple: bool) -> Result<PathBuf> {
+    let target = if dest.is_dir() {
+        dest.join(file.file_name().unwrap_or_default())
+    } else if multiple {
+        return Err(eyre!(
+            "Destination \"{}\" is not a directory",
+            dest.to_string_lossy()
+        ));
+    } else {
+        dest.to_path_buf()
+    };
+
+    if target.exists() {
+        return Err(eyre!("\"{}\" already exists", target.to_string_lossy()));
+    }
+    if target.starts_with(file) {
+        return Err(eyre!(
+            "Can not put \"{}\" inside of itself",
+            file.to_string_lossy()
+        ));
+    }
+    Ok(target)
+}
+
+async fn copy_entry(from: &Path, to: &Path) -> Result<()> {
+    let mut stack = vec![(from.to_path_buf(), to.to_path_buf())];
+    while let Some((from, to)) = stack.pop() {
+        let kind = fs::symlink_metadata(&from).await?.file_type();
+        if kind.is_dir() {
+            fs::create_dir_all(&to).await?;
+            let mut entries = fs::read_dir(&from).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                stack.push((entry.path(), to.join(entry.file_name())));
+            }
+        } else if kind.is_symlink() {
+            copy_symlink(&from, &to).await?;
+        } else {
+            fs::copy(&from, &to).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn copy_symlink(from: &Path, to: &Path) -> Result<()> {
+    fs::symlink(fs::read_link(from).await?, to).await?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn copy_symlink(from: &Path, to: &Path) -> Result<()> {
+    fs::copy(from, to).await?;
+    Ok(())
+}
+
+pub async fn copy_files(files: &[PathBuf], dest: &Path) -> Result<()> {
+    for file in files {
+        let target = get_destination(file, dest, files.len() > 1)?;
+        copy_entry(file, &target).await?;
+    }
+    Ok(())
+}
+
+pub async fn move_files(files: &[PathBuf], dest: &Path) -> Result<()> {
+    for file in files {
+        let target = get_destination(file, dest, files.len() > 1)?;
+        match fs::rename(file, &target).await {
+            Ok(()) => {}
+            // renaming fails across filesystems, so fall back to copying
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                copy_entry(file, &target).await?;
+                delete_file(file).await?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Shared with a background job, counts the bytes it got through
+#[derive(Default)]
+pub struct Progress {
+    done: AtomicU64,
+    total: AtomicU64,
+}
+
+impl Progress {
+    pub fn percent(&self) -> Option<u64> {
+        let total = self.total.load(Ordering::Relaxed);
+        (total > 0).then(|| (self.done.load(Ordering::Relaxed) * 100 / total).min(100))
+    }
+
+    fn add(&self, bytes: u64) {
+        self.done.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+/// Counts what is read from `inner` as progress
+struct Counting<'a, R> {
+    inner: R,
+    progress: &'a Progress,
+}
+
+impl<R: Read> Read for Counting<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.progress.add(n as u64);
+        Ok(n)
+    }
+}
+
+/// Every entry below `files`, paired with its name inside the archive. Links are not followed
+fn walk(files: &[PathBuf]) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut entries = vec![];
+    let mut stack = files
+        .iter()
+        .rev()
+        .map(|f| (f.clone(), PathBuf::from(f.file_name().unwrap_or_default())))
+        .collect::<Vec<_>>();
+    while let Some((path, name)) = stack.pop() {
+        if std::fs::symlink_metadata(&path)?.is_dir() {
+            let mut children = std::fs::read_dir(&path)?
+                .map(|e| Ok(e?.file_name()))
+                .collect::<Result<Vec<_>>>()?;
+            children.sort_by(|a, b| b.cmp(a));
+            stack.extend(children.into_iter().map(|c| (path.join(&c), name.join(&c))));
+        }
+        entries.push((path, name));
+    }
+    Ok(entries)
+}
+
+/// Packs `files` into a new archive at `dest`, the format is picked by its name. Blocks until
+/// done, so it is meant to run as a job
+pub fn compress(files: &[PathBuf], dest: &Path, progress: &Progress) -> Result<()> {
+    let kind = ArchiveKind::detect(dest).ok_or_else(|| {
+        eyre!(
+            "Unknown archive type: \"{}\", use .tar.gz, .tar.zst or .zip",
+            dest.to_string_lossy()
+        )
+    })?;
+    if dest.exists() {
+        return Err(eyre!("\"{}\" already exists", dest.to_string_lossy()));
+    }
+    let entries = walk(files)?;
+    let total = entries
+        .iter()
+        .filter_map(|(path, _)| std::fs::symlink_metadata(path).ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum();
+    progress.total.store(total, Ordering::Relaxed);
+
+    let file = std::fs::File::create(dest)?;
+    let result = match kind {
+        ArchiveKind::Tar => write_tar(file, &entries, progress).map(drop),
+        ArchiveKind::TarGz => {
+            let gz = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            write_tar(gz, &entries, progress).and_then(|gz| Ok(gz.finish().map(drop)?))
+        }
+        ArchiveKind::TarZst => zstd::Encoder::new(file, 0)
+            .map_err(Into::into)
+            .and_then(|zst| write_tar(zst, &entries, progress))
+            .and_then(|zst| Ok(zst.finish().map(drop)?)),
+        ArchiveKind::Zip => write_zip(file, &entries, progress),
+    };
+    // do not leave half an archive behind
+    if result.is_err() {
+        let _ = std::fs::remove_file(dest);
+    }
+    result.wrap_err_with(|| format!("Could not create \"{}\"", dest.to_string_lossy()))
+}
+
+fn write_tar<W: Write>(
+    writer: W,
+    entries: &[(PathBuf, PathBuf)],
+    progress: &Progress,
+) -> Result<W> {
+    let mut tar = tar::Builder::new(writer);
+    tar.follow_symlinks(false);
+    for (path, name) in entries {
+        let metadata = std::fs::symlink_metadata(path)?;
+        if metadata.is_file() {
+            let mut header = tar::Header::new_gnu();
+            header.set_metadata(&metadata);
+            let inner = std::fs::File::open(path)?;
+            tar.append_data(&mut header, name, Counting { inner, progress })?;
+        } else {
+            tar.append_path_with_name(path, name)?;
+        }
+    }
+    Ok(tar.into_inner()?)
+}
+
+fn write_zip(
+    file: std::fs::File,
+    entries: &[(PathBuf, PathBuf)],
+    progress: &Progress,
+) -> Result<()> {
+    let mut zip = zip::ZipWriter::new(file);
+    for (path, name) in entries {
+        let metadata = std::fs::symlink_metadata(path)?;
+        let mut options =
+            zip::write::SimpleFileOptions::default().large_file(metadata.len() > u32::MAX.into());
+        if let Some(mode) = get_mode(&metadata) {
+            options = options.unix_permissions(mode);
+        }
+        if let Some(time) = metadata.modified().ok().and_then(zip_time) {
+            options = options.last_modified_time(time);
+        }
+        // zip always uses `/`
+        let name = name.to_string_lossy().replace('\\', "/");
+        if metadata.is_dir() {
+            zip.add_directory(name, options)?;
+        } else if metadata.is_symlink() {
+            let target = std::fs::read_link(path)?;
+            zip.add_symlink(name, target.to_string_lossy(), options)?;
+        } else {
+            zip.start_file(name, options)?;
+            let inner = std::fs::File::open(path)?;
+            io::copy(&mut Counting { inner, progress }, &mut zip)?;
+        }
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// Where an entry called `name` goes inside `dest`. `None` when it would end up outside, be it
+/// through `..` or a link that was extracted before
+fn extract_target(dest: &Path, name: &Path) -> Option<PathBuf> {
+    let mut target = dest.to_path_buf();
+    for component in clean(name)?.components() {
+        target.push(component);
+        if std::fs::symlink_metadata(&target).is_ok_and(|m| m.is_symlink()) {
+            return None;
+        }
+    }
+    Some(target)
+}
+
+/// Makes sure extracting never replaces what is already there
+fn check_free(target: &Path) -> Result<()> {
+    match std::fs::symlink_metadata(target) {
+        Ok(m) if !m.is_dir() => Err(eyre!("\"{}\" already exists", target.to_string_lossy())),
+        _ => Ok(()),
+    }
+}
+
+/// Unpacks the archive `file` into `dest`, which is created when needed. Entries that would end
+/// up outside of `dest` are skipped, returns how many were. Blocks until done, so it is meant
+/// to run as a job
+pub fn extract(file: &Path, dest: &Path, progress: &Progress) -> Result<usize> {
+    let kind = ArchiveKind::detect(file)
+        .ok_or_else(|| eyre!("Not an archive: \"{}\"", file.to_string_lossy()))?;
+    std::fs::create_dir_all(dest)?;
+    let dest = dest.canonicalize()?;
+    let inner = std::fs::File::open(file)?;
+    progress
+        .total
+        .store(inner.metadata()?.len(), Ordering::Relaxed);
+
+    let reader = Counting { inner, progress };
+    match kind {
+        ArchiveKind::Tar => extract_tar(reader, &dest),
+        ArchiveKind::TarGz => extract_tar(flate2::read::GzDecoder::new(reader), &dest),
+        ArchiveKind::TarZst => extract_tar(zstd::Decoder::new(reader)?, &dest),
+        ArchiveKind::Zip => extract_zip(std::fs::File::open(file)?, &dest, progress),
+    }
+    .wrap_err_with(|| format!("Could not extract \"{}\"", file.to_string_lossy()))
+}
+
+fn extract_tar(reader: impl Read, dest: &Path) -> Result<usize> {
+    let mut tar = tar::Archive::new(reader);
+    let mut skipped = 0;
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let Some(target) = extract_target(dest, &entry.path()?) else {
+            skipped += 1;
+            continue;
+        };
+        check_free(&target)?;
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if entry.header().entry_type().is_hard_link() {
+            // the file it links to has to be inside as well
+            let link = entry.link_name()?;
+            let Some(source) = link.and_then(|l| extract_target(dest, &l)) else {
+                skipped += 1;
+                continue;
+            };
+            std::fs::hard_link(source, &target)?;
+            continue;
+        }
+        entry.unpack(&target)?;
+    }
+    Ok(skipped)
+}
+
+fn extract_zip(file: std::fs::File, dest: &Path, progress: &Progress) -> Result<usize> {
+    let mut zip = zip::ZipArchive::new(file)?;
+    let mut skipped = 0;
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        progress.add(entry.compressed_size());
+        let Some(target) = entry
+            .enclosed_name()
+            .and_then(|name| extract_target(dest, &name))
+        else {
+            skipped += 1;
+            continue;
+        };
+        check_free(&target)?;
+        if entry.is_dir() {
+            std::fs::create_dir_all(&target)?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if entry.is_symlink() {
+            let mut link = String::new();
+            entry.read_to_string(&mut link)?;
+            extract_symlink(Path::new(&link), &target)?;
+            continue;
+        }
+        io::copy(&mut entry, &mut std::fs::File::create(&target)?)?;
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&target, std::fs::Permissions::from_mode(mode & 0o777))?;
+        }
+    }
+    Ok(skipped)
+}
+
+#[cfg(unix)]
+fn extract_symlink(link: &Path, target: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(link, target)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn extract_symlink(_link: &Path, _target: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn archive_tests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("src/deep")).unwrap();
+        fs::write(dir.join("src/deep/a.txt"), "hello").unwrap();
+
+        for name in ["out.tar.gz", "out.tar.zst", "out.zip"] {
+            let progress = Progress::default();
+            compress(&[dir.join("src")], &dir.join(name), &progress).unwrap();
+            assert_eq!(progress.percent(), Some(100));
+            assert!(compress(&[dir.join("src")], &dir.join(name), &progress).is_err());
+
+            let dest = dir.join(format!("{name}.d"));
+            assert_eq!(extract(&dir.join(name), &dest, &progress).unwrap(), 0);
+            let content = fs::read_to_string(dest.join("src/deep/a.txt")).unwrap();
+            assert_eq!(content, "hello");
+            // never replaces what is there
+            assert!(extract(&dir.join(name), &dest, &progress).is_err());
+        }
+        assert!(compress(
+            &[dir.join("src")],
+            &dir.join("out.rar"),
+            &Progress::default()
+        )
+        .is_err());
+
+        // a link followed by a file written through it
+        let path = dir.join("evil.tar");
+        let mut tar = tar::Builder::new(fs::File::create(&path).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        tar.append_link(&mut header, "link", dir).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        tar.append_data(&mut header, "link/escaped", &b"evil"[..])
+            .unwrap();
+        tar.into_inner().unwrap();
+
+        let dest = dir.join("evil");
+        assert_eq!(extract(&path, &dest, &Progress::default()).unwrap(), 1);
+        assert!(!dir.join("escaped").exists());
+
+        let path = dir.join("evil.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        zip.start_file("../escaped", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"evil").unwrap();
+        zip.finish().unwrap();
+        assert_eq!(extract(&path, &dest, &Progress::default()).unwrap(), 1);
+        assert!(!dir.join("escaped").exists());
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Expands `~` and environment variables (`$VAR` or `${VAR}`), relative paths are joined onto
+/// `base`
+pub fn expand(input: &str, base: &Path) -> PathBuf {
+    let input = expand_vars(input, |name| std::env::var(name).ok());
+    let path = match input.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches(['/', '\\'])),
+            None => PathBuf::from(&input),
+        },
+        _ => PathBuf::from(&input),
+    };
+    base.join(path)
+}
+
+// unknown variables are left as they are
+fn expand_vars(input: &str, var: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut rest = input;
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        let (name, len) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        match var(name) {
+            Some(value) if !name.is_empty() => out.push_str(&value),
+            _ => out.push_str(&rest[i..i + 1 + len]),
+        }
+        rest = &after[len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The entry of `dir` that `input` ends up in, e.g. `sub` for `./sub/file`. `None` when it is
+/// outside of `dir`
+pub fn entry_in(dir: &Path, input: &str) -> Option<OsString> {
+    let mut path = PathBuf::new();
+    for component in dir.join(input).components() {
+        match component {
+            Component::ParentDir => {
+                path.pop();
+            }
+            Component::CurDir => {}
+            c => path.push(c),
+        }
+    }
+    let first = path.strip_prefix(dir).ok()?.components().next()?;
+    Some(first.as_os_str().to_os_string())
+}
+
+fn split_input(input: &str) -> (&str, &str) {
+    match input.rfind(['/', '\\']) {
+        Some(i) => (&input[..=i], &input[i + 1..]),
+        None => ("", input),
+    }
+}
+
+/// The directory names that can complete the last part of `input`
+pub fn complete_candidates(input: &str, base: &Path) -> Vec<String> {
+    let (dir, prefix) = split_input(input);
+    let Ok(entries) = fs::read_dir(expand(dir, base)) else {
+        return vec![];
+    };
+
+    let mut names = entries
+        .filter_map(|e| e.ok())
+        // follows symlinks to directories
+        .filter(|e| e.path().is_dir())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|n| n.starts_with(prefix) && (prefix.starts_with('.') || !n.starts_with('.')))
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Completes the last part of `input` as far as all matching directories agree
+pub fn complete(input: &str, base: &Path) -> String {
+    let (dir, _) = split_input(input);
+    let candidates = complete_candidates(input, base);
+    match candidates.as_slice() {
+        [] => input.to_string(),
+        [name] => format!("{dir}{name}{}", std::path::MAIN_SEPARATOR),
+        [first, rest @ ..] => {
+            let mut common = first.as_str();
+            for name in rest {
+                let len = common
+                    .char_indices()
+                    .zip(name.chars())
+                    .find(|((_, a), b)| a != b)
+                    .map(|((i, _), _)| i)
+                    .unwrap_or(common.len().min(name.len()));
+                common = &common[..len];
+            }
+            format!("{dir}{common}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn expand_tests() {
+        let base = Path::new("/base");
+        assert_eq!(PathBuf::from("/base/dir"), expand("dir", base));
+        assert_eq!(PathBuf::from("/abs/dir"), expand("/abs/dir", base));
+
+        let var = |name: &str| (name == "VAR").then(|| "var".to_string());
+        let inputs = [
+            ("$VAR/dir", "var/dir"),
+            ("/${VAR}s", "/vars"),
+            ("$MISSING/a", "$MISSING/a"),
+            ("a$", "a$"),
+            ("${VAR", "${VAR"),
+        ];
+        for (input, expected) in inputs {
+            assert_eq!(expected, expand_vars(input, var));
+        }
+        assert_eq!(entry_in(base, "./a"), Some("a".into()));
+        assert_eq!(entry_in(base, "sub/../b/c/"), Some("b".into()));
+        assert_eq!(entry_in(base, "../a"), None);
+        assert_eq!(entry_in(base, "/abs/a"), None);
+        assert_eq!(entry_in(base, "."), None);
+
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(home.join("docs"), expand("~/docs", base));
+            assert_eq!(PathBuf::from("/base/~docs"), expand("~docs", base));
+        }
+    }
+
+    #[test]
+    fn complete_tests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        for sub in ["projects", "pictures", "public", ".private"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        fs::write(dir.join("photo.png"), "").unwrap();
+
+        assert_eq!(
+            complete_candidates("p", dir),
+            ["pictures", "projects", "public"]
+        );
+        assert_eq!(
+            complete("pr", dir),
+            format!("projects{}", std::path::MAIN_SEPARATOR)
+        );
+        assert_eq!(
+            complete("pi", dir),
+            format!("pictures{}", std::path::MAIN_SEPARATOR)
+        );
+        assert_eq!(complete("p", dir), "p");
+        assert_eq!(
+            complete("pu", dir),
+            format!("public{}", std::path::MAIN_SEPARATOR)
+        );
+        assert_eq!(complete_candidates(".p", dir), [".private"]);
+        assert_eq!(complete("nothing", dir), "nothing");
+
+        let input = format!("{}/pro", dir.display());
+        assert_eq!(
+            complete(&input, Path::new("/")),
+            format!("{}/projects/", dir.display())
+        );
+    }
+}
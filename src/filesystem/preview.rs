@@ -0,0 +1,473 @@
+use std::{
+    fs,
+    io::{Read, Seek, SeekFrom},
+    ops::Range,
+    path::Path,
+};
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use eyre::{Context, Result};
+
+pub const DEFAULT_MAX_BYTES: u64 = 1024 * 1024;
+pub const DEFAULT_MAX_LINES: usize = 10_000;
+// bytes in one row of a hex dump
+pub const HEX_WIDTH: usize = 16;
+// only the start is looked at to tell text from binary
+const SNIFF_LEN: usize = 8 * 1024;
+
+/// How much of a file is read for the preview
+#[derive(Clone, Copy)]
+pub struct PreviewOptions {
+    pub max_bytes: u64,
+    pub max_lines: usize,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_lines: DEFAULT_MAX_LINES,
+        }
+    }
+}
+
+impl PreviewOptions {
+    // a hex dump row counts as a line
+    fn max_binary(&self) -> u64 {
+        self.max_bytes
+            .min((self.max_lines * HEX_WIDTH) as u64)
+            .max(HEX_WIDTH as u64)
+    }
+}
+
+pub enum Data {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Mixed,
+}
+
+impl LineEnding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Lf => "LF",
+            Self::Crlf => "CRLF",
+            Self::Mixed => "mixed",
+        }
+    }
+}
+
+/// The part of a file shown in the preview
+pub struct Content {
+    pub data: Data,
+    pub size: u64,
+    // where in the file binary data starts
+    pub offset: u64,
+    // parts of the file before or after were left out
+    pub cut_start: bool,
+    pub cut_end: bool,
+    // only known for text
+    pub encoding: Option<&'static Encoding>,
+    pub line_ending: Option<LineEnding>,
+}
+
+/// Reads the start of a file, the encoding is detected unless one is given
+pub fn read_head(
+    path: &Path,
+    opts: PreviewOptions,
+    encoding: Option<&'static Encoding>,
+) -> Result<Content> {
+    read(path, opts, false, encoding)
+}
+
+/// Reads the end of a file, like `tail`
+pub fn read_tail(
+    path: &Path,
+    opts: PreviewOptions,
+    encoding: Option<&'static Encoding>,
+) -> Result<Content> {
+    read(path, opts, true, encoding)
+}
+
+/// Reads a file as binary from `offset`, rounded down to the start of its hex dump row
+pub fn read_binary_at(path: &Path, opts: PreviewOptions, offset: u64) -> Result<Content> {
+    let mut file = open(path)?;
+    let size = file.metadata()?.len();
+    let offset = offset.min(size.saturating_sub(1)) / HEX_WIDTH as u64 * HEX_WIDTH as u64;
+    file.seek(SeekFrom::Start(offset))?;
+
+    let mut bytes = vec![];
+    file.take(opts.max_binary()).read_to_end(&mut bytes)?;
+    Ok(Content {
+        cut_start: offset > 0,
+        cut_end: offset + (bytes.len() as u64) < size,
+        data: Data::Binary(bytes),
+        size,
+        offset,
+        encoding: None,
+        line_ending: None,
+    })
+}
+
+fn open(path: &Path) -> Result<fs::File> {
+    fs::File::open(path).wrap_err_with(|| format!("Could not open file: \"{}\"", path.display()))
+}
+
+fn read(
+    path: &Path,
+    opts: PreviewOptions,
+    tail: bool,
+    encoding: Option<&'static Encoding>,
+) -> Result<Content> {
+    let mut file = open(path)?;
+    let size = file.metadata()?.len();
+    let start = if tail {
+        // keeps UTF-16 aligned
+        size.saturating_sub(opts.max_bytes).next_multiple_of(2)
+    } else {
+        0
+    };
+    file.seek(SeekFrom::Start(start))?;
+
+    let mut bytes = vec![];
+    file.take(opts.max_bytes).read_to_end(&mut bytes)?;
+    let mut cut_start = start > 0;
+    let mut cut_end = start + (bytes.len() as u64) < size;
+
+    let Some((text, encoding)) = decode(&bytes, encoding, (cut_start, cut_end)) else {
+        if tail {
+            // the last rows, the final one may be partly filled
+            let rows = size.div_ceil(HEX_WIDTH as u64) * HEX_WIDTH as u64;
+            return read_binary_at(path, opts, rows.saturating_sub(opts.max_binary()));
+        }
+        bytes.truncate(opts.max_binary() as usize);
+        return Ok(Content {
+            cut_end: (bytes.len() as u64) < size,
+            data: Data::Binary(bytes),
+            size,
+            offset: 0,
+            cut_start: false,
+            encoding: None,
+            line_ending: None,
+        });
+    };
+
+    let line_ending = line_ending(&text);
+    let mut text = text.replace("\r\n", "\n");
+    // the first line is most likely cut in half
+    if start > 0 {
+        if let Some((_, rest)) = text.split_once('\n') {
+            text = rest.to_string();
+        }
+    }
+
+    let count = text.lines().count();
+    if count > opts.max_lines {
+        let lines = text.lines();
+        text = if tail {
+            cut_start = true;
+            lines
+                .skip(count - opts.max_lines)
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            cut_end = true;
+            lines.take(opts.max_lines).collect::<Vec<_>>().join("\n")
+        };
+    }
+    Ok(Content {
+        data: Data::Text(text),
+        size,
+        offset: 0,
+        cut_start,
+        cut_end,
+        encoding: Some(encoding),
+        line_ending,
+    })
+}
+
+/// Decodes text, `None` when it looks like binary. Without a forced encoding the BOM is used,
+/// then UTF-16 without a BOM, UTF-8 and last a guess between the legacy encodings. `cut` is
+/// whether the start and the end of the file were left out of `bytes`
+pub fn decode(
+    bytes: &[u8],
+    forced: Option<&'static Encoding>,
+    cut: (bool, bool),
+) -> Option<(String, &'static Encoding)> {
+    if let Some(encoding) = forced {
+        // a BOM of that encoding is still left out
+        let (text, _) = encoding.decode_with_bom_removal(bytes);
+        return Some((text.into_owned(), encoding));
+    }
+    if let Some((encoding, len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[len..]);
+        return Some((text.into_owned(), encoding));
+    }
+
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+    let (text, encoding) = if sample.contains(&0) {
+        let encoding = guess_utf16(sample)?;
+        let (text, _) = encoding.decode_without_bom_handling(bytes);
+        (text.into_owned(), encoding)
+    } else {
+        match std::str::from_utf8(&bytes[text_range(bytes, cut)]) {
+            Ok(text) => (text.to_string(), UTF_8),
+            Err(_) => {
+                let mut detector = EncodingDetector::new();
+                detector.feed(sample, true);
+                let encoding = detector.guess(None, false);
+                let (text, _) = encoding.decode_without_bom_handling(bytes);
+                (text.into_owned(), encoding)
+            }
+        }
+    };
+    (!looks_binary(&text)).then_some((text, encoding))
+}
+
+// ascii text in UTF-16 has every other byte set to zero
+fn guess_utf16(sample: &[u8]) -> Option<&'static Encoding> {
+    let pairs = sample.len() / 2;
+    let zeros = |parity: usize| {
+        sample
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros(0), zeros(1));
+    if odd * 10 > pairs * 4 && even * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even * 10 > pairs * 4 && odd * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+// lots of control characters or bytes that could not be decoded
+fn looks_binary(text: &str) -> bool {
+    let mut total = 0;
+    let mut odd = 0;
+    for c in text.chars().take(SNIFF_LEN) {
+        total += 1;
+        if c == char::REPLACEMENT_CHARACTER
+            || (c.is_control() && !matches!(c, '\n' | '\r' | '\t' | '\x0c' | '\x1b'))
+        {
+            odd += 1;
+        }
+    }
+    odd * 10 > total
+}
+
+pub fn line_ending(text: &str) -> Option<LineEnding> {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    match (lf, crlf) {
+        (0, 0) => None,
+        (_, 0) => Some(LineEnding::Lf),
+        (0, _) => Some(LineEnding::Crlf),
+        _ => Some(LineEnding::Mixed),
+    }
+}
+
+// a character cut in half where the read was cut is left out
+fn text_range(bytes: &[u8], (cut_start, cut_end): (bool, bool)) -> Range<usize> {
+    let start = match cut_start {
+        true => bytes
+            .iter()
+            .take(3)
+            .take_while(|b| (**b & 0xC0) == 0x80)
+            .count(),
+        false => 0,
+    };
+    let end = match std::str::from_utf8(&bytes[start..]) {
+        Err(e) if cut_end && e.error_len().is_none() => start + e.valid_up_to(),
+        _ => bytes.len(),
+    };
+    start..end
+}
+
+/// The hex column of a dump row, padded so the ascii column lines up
+pub fn hex_row(row: &[u8]) -> String {
+    let mut out = String::new();
+    for i in 0..HEX_WIDTH {
+        if i == HEX_WIDTH / 2 {
+            out.push(' ');
+        }
+        match row.get(i) {
+            Some(b) => out.push_str(&format!("{b:02x} ")),
+            None => out.push_str("   "),
+        }
+    }
+    out
+}
+
+/// The ascii column of a dump row, bytes that are not printable become `.`
+pub fn ascii_row(row: &[u8]) -> String {
+    row.iter()
+        .map(|b| match b {
+            0x20..=0x7e => *b as char,
+            _ => '.',
+        })
+        .collect()
+}
+
+/// Parses a hex offset, with or without `0x`
+pub fn parse_offset(input: &str) -> Result<u64> {
+    let input = input.trim();
+    let digits = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input);
+    u64::from_str_radix(digits, 16).wrap_err_with(|| format!("Not a hex offset: \"{input}\""))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn text(content: Content) -> String {
+        match content.data {
+            Data::Text(text) => text,
+            Data::Binary(_) => panic!("read as binary"),
+        }
+    }
+
+    #[test]
+    fn read_tests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("preview");
+        let content = (1..=10).map(|i| format!("line {i}\n")).collect::<String>();
+        fs::write(&file, &content).unwrap();
+
+        let opts = PreviewOptions {
+            max_bytes: 1024,
+            max_lines: 100,
+        };
+        let head = read_head(&file, opts, None).unwrap();
+        assert!(!head.cut_end);
+        assert_eq!(text(head), content);
+
+        let opts = PreviewOptions {
+            max_bytes: 1024,
+            max_lines: 3,
+        };
+        let head = read_head(&file, opts, None).unwrap();
+        assert!(head.cut_end);
+        assert_eq!(text(head), "line 1\nline 2\nline 3");
+        let tail = read_tail(&file, opts, None).unwrap();
+        assert!(tail.cut_start);
+        assert_eq!(text(tail), "line 8\nline 9\nline 10");
+
+        // the partial line at the cut is dropped
+        let opts = PreviewOptions {
+            max_bytes: 12,
+            max_lines: 100,
+        };
+        let tail = read_tail(&file, opts, None).unwrap();
+        assert_eq!(tail.size, content.len() as u64);
+        assert_eq!(text(tail), "line 10\n");
+
+        fs::write(&file, "é".repeat(10)).unwrap();
+        let opts = PreviewOptions {
+            max_bytes: 5,
+            max_lines: 100,
+        };
+        assert_eq!(text(read_head(&file, opts, None).unwrap()), "éé");
+        assert_eq!(text(read_tail(&file, opts, None).unwrap()), "éé");
+    }
+
+    #[test]
+    fn binary_tests() {
+        let text = |bytes: &[u8]| decode(bytes, None, (false, false)).map(|(t, e)| (t, e.name()));
+        assert_eq!(text(b"plain text"), Some(("plain text".into(), "UTF-8")));
+        assert!(text(b"text\0with a nul").is_none());
+        assert!(text(&[0x01, 0x02, 0x81, 0x90, b'a', 0x03]).is_none());
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("hex");
+        fs::write(&file, (0..=255u8).collect::<Vec<_>>()).unwrap();
+        let opts = PreviewOptions {
+            max_bytes: 1024,
+            max_lines: 2,
+        };
+        let head = read_head(&file, opts, None).unwrap();
+        assert!(matches!(head.data, Data::Binary(ref b) if b.len() == 32));
+        assert!(head.cut_end);
+
+        let at = read_binary_at(&file, opts, 0x25).unwrap();
+        assert_eq!(at.offset, 0x20);
+        assert!(matches!(at.data, Data::Binary(ref b) if b[0] == 0x20));
+
+        let tail = read_tail(&file, opts, None).unwrap();
+        assert_eq!(tail.offset, 0xe0);
+        assert!(!tail.cut_end);
+    }
+
+    #[test]
+    fn encoding_tests() {
+        let text = |bytes: &[u8]| decode(bytes, None, (false, false)).map(|(t, e)| (t, e.name()));
+        assert_eq!(
+            text(b"caf\xe9 au lait, cr\xe8me br\xfbl\xe9e"),
+            Some(("café au lait, crème brûlée".into(), "windows-1252"))
+        );
+        assert_eq!(text(b"\xef\xbb\xbfbom"), Some(("bom".into(), "UTF-8")));
+        assert_eq!(text(b"\xff\xfeh\0i\0"), Some(("hi".into(), "UTF-16LE")));
+        assert_eq!(text(b"\0h\0e\0l\0l\0o"), Some(("hello".into(), "UTF-16BE")));
+        // "日本語のテキスト" in Shift_JIS
+        let sjis = b"\x93\xfa\x96\x7b\x8c\xea\x82\xcc\x83\x65\x83\x4c\x83\x58\x83\x67";
+        assert_eq!(text(sjis), Some(("日本語のテキスト".into(), "Shift_JIS")));
+        let latin1 = Encoding::for_label(b"latin1");
+        let forced = |bytes: &[u8], encoding| decode(bytes, encoding, (false, false)).unwrap().0;
+        assert_eq!(forced(b"\xe9", latin1), "é");
+        assert_eq!(forced(b"\xef\xbb\xbfbom", Some(UTF_8)), "bom");
+        assert_eq!(
+            forced(b"\xff\xfeh\0i\0", Encoding::for_label(b"utf-16le")),
+            "hi"
+        );
+
+        // only a read that was cut can end in half a character
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("latin1");
+        let opts = PreviewOptions {
+            max_bytes: 1024,
+            max_lines: 100,
+        };
+        let read = |content: &[u8]| {
+            fs::write(&file, content).unwrap();
+            let head = read_head(&file, opts, None).unwrap();
+            let encoding = head.encoding.unwrap().name();
+            (self::text(head), encoding)
+        };
+        assert_eq!(read(b"caf\xe9"), ("café".into(), "windows-1252"));
+        assert_eq!(read(b"\xa9 2024"), ("© 2024".into(), "windows-1252"));
+        assert_eq!(
+            decode(b"\x80caf\xc3", None, (true, true)),
+            Some(("caf".into(), UTF_8))
+        );
+
+        assert_eq!(line_ending("a\nb\n"), Some(LineEnding::Lf));
+        assert_eq!(line_ending("a\r\nb\r\n"), Some(LineEnding::Crlf));
+        assert_eq!(line_ending("a\r\nb\n"), Some(LineEnding::Mixed));
+        assert_eq!(line_ending("a"), None);
+    }
+
+    #[test]
+    fn hex_tests() {
+        assert_eq!(
+            hex_row(b"0123456789abcdef"),
+            "30 31 32 33 34 35 36 37  38 39 61 62 63 64 65 66 "
+        );
+        assert_eq!(hex_row(b"ab").len(), hex_row(b"0123456789abcdef").len());
+        assert_eq!(ascii_row(b"a\0b\n~"), "a.b.~");
+        assert_eq!(parse_offset("0x1f").unwrap(), 31);
+        assert_eq!(parse_offset("100").unwrap(), 256);
+        assert!(parse_offset("xyz").is_err());
+    }
+}
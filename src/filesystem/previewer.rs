@@ -0,0 +1,270 @@
+use std::{
+    collections::HashMap,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    str::FromStr,
+    sync::{mpsc, Mutex},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use eyre::{eyre, Context, Result};
+
+pub const DEFAULT_TIMEOUT: u64 = 5;
+// outputs kept around, older ones are dropped once there are more
+const CACHE_SIZE: usize = 64;
+
+/// Which files a previewer is used for
+#[derive(Clone, PartialEq, Debug)]
+pub enum Matcher {
+    // without the dot, e.g. `pdf` or `tar.gz`
+    Extension(String),
+    // `video/*` matches any video
+    Mime(String),
+}
+
+impl Matcher {
+    fn matches(&self, name: &str, mime: Option<&str>) -> bool {
+        match (self, mime) {
+            (Self::Extension(ext), _) => name.ends_with(&format!(".{ext}")),
+            (Self::Mime(pattern), Some(mime)) => match pattern.strip_suffix("/*") {
+                Some(kind) => mime.split('/').next() == Some(kind),
+                None => mime == pattern,
+            },
+            (Self::Mime(_), None) => false,
+        }
+    }
+}
+
+/// A command that previews files it matches, e.g. `pdf=pdftotext "$1" -`. It runs with `sh`,
+/// the path of the file is `$1`
+#[derive(Clone, PartialEq, Debug)]
+pub struct Previewer {
+    pub matcher: Matcher,
+    pub command: String,
+}
+
+impl FromStr for Previewer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (matcher, command) = s
+            .split_once('=')
+            .ok_or("expected EXTENSION=COMMAND or MIME=COMMAND")?;
+        let (matcher, command) = (matcher.trim().to_lowercase(), command.trim());
+        if matcher.is_empty() || command.is_empty() {
+            return Err("expected EXTENSION=COMMAND or MIME=COMMAND".to_string());
+        }
+        let matcher = match matcher.contains('/') {
+            true => Matcher::Mime(matcher),
+            false => Matcher::Extension(matcher.trim_start_matches('.').to_string()),
+        };
+        Ok(Self {
+            matcher,
+            command: command.to_string(),
+        })
+    }
+}
+
+impl Previewer {
+    /// The program that is run, shown above its output
+    pub fn program(&self) -> &str {
+        self.command.split_whitespace().next().unwrap_or_default()
+    }
+}
+
+/// The MIME type of a file, guessed by its extension or else asked from `file`
+fn mime_type(path: &Path) -> Option<String> {
+    if let Some(mime) = mime_guess::from_path(path).first() {
+        return Some(mime.essence_str().to_string());
+    }
+    let output = Command::new("file")
+        .args(["--brief", "--mime-type"])
+        .arg(path)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let mime = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && mime.contains('/')).then_some(mime)
+}
+
+/// Runs `previewer` on `path`, its output is cut after `max_bytes`. It is stopped once it takes
+/// longer than `timeout`
+pub fn run(
+    previewer: &Previewer,
+    path: &Path,
+    timeout: Duration,
+    max_bytes: u64,
+) -> Result<String> {
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        // errors are shown in the preview
+        .arg(format!("exec 2>&1\n{}", previewer.command))
+        .arg("sh")
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped());
+    // in its own group, so what it starts can be stopped with it
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = command
+        .spawn()
+        .wrap_err_with(|| format!("Could not run previewer: \"{}\"", previewer.command))?;
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut output = vec![];
+        let _ = stdout.take(max_bytes).read_to_end(&mut output);
+        let _ = sender.send(output);
+    });
+
+    let output = receiver.recv_timeout(timeout);
+    // still running once it wrote too much, or when it took too long
+    if !matches!(child.try_wait(), Ok(Some(_))) || output.is_err() {
+        kill(&mut child);
+    }
+    let status = child.wait()?;
+    let output = output.map_err(|_| {
+        eyre!(
+            "Previewer \"{}\" took longer than {}s",
+            previewer.program(),
+            timeout.as_secs_f32()
+        )
+    })?;
+    if output.is_empty() && !status.success() {
+        return Err(eyre!(
+            "Previewer \"{}\" failed: {status}",
+            previewer.program()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Stops `child` and everything it started
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    // SAFETY: only sends a signal. The group is still there, the child is not waited for yet
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    #[cfg(not(unix))]
+    let _ = child.kill();
+}
+
+/// The configured previewers, with the outputs of the files they already previewed
+#[derive(Default)]
+pub struct Previewers {
+    list: Vec<Previewer>,
+    timeout: Duration,
+    cache: Mutex<HashMap<(PathBuf, Option<SystemTime>), String>>,
+}
+
+impl Previewers {
+    pub fn new(list: Vec<Previewer>, timeout: Duration) -> Self {
+        Self {
+            list,
+            timeout,
+            cache: Mutex::default(),
+        }
+    }
+
+    /// The first previewer matching `path`
+    pub fn find(&self, path: &Path) -> Option<&Previewer> {
+        if self.list.is_empty() {
+            return None;
+        }
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        // only looked up when it is needed
+        let mime = self
+            .list
+            .iter()
+            .any(|p| matches!(p.matcher, Matcher::Mime(_)))
+            .then(|| mime_type(path))
+            .flatten();
+        self.list
+            .iter()
+            .find(|p| p.matcher.matches(&name, mime.as_deref()))
+    }
+
+    /// The output of the previewer for `path`, `None` when there is none for it. It only runs
+    /// again once the file was modified
+    pub fn preview(
+        &self,
+        path: &Path,
+        modified: Option<SystemTime>,
+        max_bytes: u64,
+    ) -> Option<(&Previewer, Result<String>)> {
+        let previewer = self.find(path)?;
+        let key = (path.to_path_buf(), modified);
+        if let Some(output) = self.cache.lock().ok()?.get(&key) {
+            return Some((previewer, Ok(output.clone())));
+        }
+
+        let output = run(previewer, path, self.timeout, max_bytes);
+        if let (Ok(output), Ok(mut cache)) = (&output, self.cache.lock()) {
+            if cache.len() >= CACHE_SIZE {
+                cache.clear();
+            }
+            cache.insert(key, output.clone());
+        }
+        Some((previewer, output))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn previewer_tests() {
+        let pdf: Previewer = "PDF=pdftotext \"$1\" -".parse().unwrap();
+        assert_eq!(pdf.matcher, Matcher::Extension("pdf".to_string()));
+        assert_eq!(pdf.program(), "pdftotext");
+        let video: Previewer = "video/*=mediainfo \"$1\"".parse().unwrap();
+        assert!("pdf".parse::<Previewer>().is_err());
+
+        let previewers = Previewers::new(vec![pdf, video], Duration::from_secs(1));
+        let find = |path| previewers.find(Path::new(path)).map(|p| p.program());
+        assert_eq!(find("/a/b.Pdf"), Some("pdftotext"));
+        assert_eq!(find("/a/b.mp4"), Some("mediainfo"));
+        assert_eq!(find("/a/b.txt"), None);
+    }
+
+    #[test]
+    fn run_tests() {
+        let path = Path::new("a file");
+        let echo: Previewer = "x=printf '%s\\n' \"$1\"; echo oops >&2".parse().unwrap();
+        let output = run(&echo, path, Duration::from_secs(5), 1024).unwrap();
+        assert_eq!(output, "a file\noops\n");
+        assert_eq!(run(&echo, path, Duration::from_secs(5), 3).unwrap(), "a f");
+
+        let slow: Previewer = "x=sleep 5".parse().unwrap();
+        assert!(run(&slow, path, Duration::from_millis(100), 1024).is_err());
+        let fail: Previewer = "x=exit 3".parse().unwrap();
+        assert!(run(&fail, path, Duration::from_secs(5), 1024).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn timeout_tests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("pid");
+        let slow: Previewer = "x=sleep 30 & echo $! > \"$1\"; wait".parse().unwrap();
+        assert!(run(&slow, &path, Duration::from_millis(200), 1024).is_err());
+
+        // killed, at most left for its new parent to wait for
+        let pid = std::fs::read_to_string(&path).unwrap();
+        let running = || {
+            std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()))
+                .is_ok_and(|stat| !stat.contains(") Z "))
+        };
+        let started = std::time::Instant::now();
+        while running() && started.elapsed() < Duration::from_secs(1) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!running());
+    }
+}
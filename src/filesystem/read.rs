@@ -1,36 +1,90 @@
 use std::{
     ffi::OsString,
-    fs::{FileType, Metadata},
-    path::PathBuf,
+    fs::Metadata,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::SystemTime,
 };
 
 use eyre::{Context, Report, Result};
 use tokio::fs::{self, DirEntry};
 
+use super::utils::get_mode;
+use crate::shell::display_path;
+
+/// What an entry is, symlinks are not followed
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FileKind {
+    Dir,
+    File,
+    Symlink,
+    Other,
+}
+
+impl From<std::fs::FileType> for FileKind {
+    fn from(t: std::fs::FileType) -> Self {
+        if t.is_symlink() {
+            Self::Symlink
+        } else if t.is_dir() {
+            Self::Dir
+        } else if t.is_file() {
+            Self::File
+        } else {
+            Self::Other
+        }
+    }
+}
+
 pub struct File {
     pub path: PathBuf,
-    pub file_type: FileType,
+    pub kind: FileKind,
     pub name: OsString,
-    pub metadata: Metadata,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    // the permission bits, `None` where they are not available
+    pub mode: Option<u32>,
+    pub readonly: bool,
 }
 
 impl File {
     pub async fn new(d: DirEntry) -> Result<Self> {
-        Ok(Self {
-            path: d.path(),
-            file_type: d.file_type().await?,
-            name: d.file_name(),
-            metadata: d.metadata().await?,
-        })
+        let kind = d.file_type().await?.into();
+        Ok(Self::from_metadata(
+            d.path(),
+            d.file_name(),
+            kind,
+            &d.metadata().await?,
+        ))
+    }
+
+    pub fn from_metadata(path: PathBuf, name: OsString, kind: FileKind, m: &Metadata) -> Self {
+        Self {
+            path,
+            kind,
+            name,
+            size: m.len(),
+            modified: m.modified().ok(),
+            mode: get_mode(m),
+            readonly: m.permissions().readonly(),
+        }
     }
 
     pub fn is_dir(&self) -> Result<bool> {
-        Ok(self.file_type.is_dir()
-            | (self.file_type.is_symlink() && self.path.canonicalize()?.is_dir()))
+        Ok(self.kind == FileKind::Dir
+            || (self.kind == FileKind::Symlink && self.path.canonicalize()?.is_dir()))
     }
     pub fn is_file(&self) -> Result<bool> {
-        Ok(self.file_type.is_file()
-            | (self.file_type.is_symlink() && self.path.canonicalize()?.is_file()))
+        Ok(self.kind == FileKind::File
+            || (self.kind == FileKind::Symlink && self.path.canonicalize()?.is_file()))
+    }
+
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            FileKind::Symlink => "symlink",
+            FileKind::Dir => "dir",
+            FileKind::File => "file",
+            FileKind::Other => "other",
+        }
     }
 }
 
@@ -39,17 +93,18 @@ pub enum ReadRes {
     Read(Vec<File>),
 }
 
-pub async fn read_with_fallback(path: &PathBuf, fallback: PathBuf) -> Result<ReadRes> {
-    if path.exists() {
-        Ok(ReadRes::Read(read_path(path).await?))
+pub async fn read_with_fallback(path: &PathBuf, fallback: &PathBuf) -> Result<ReadRes> {
+    if let Ok(files) = read_path(path).await {
+        Ok(ReadRes::Read(files))
     } else {
         let r = eyre::eyre!(
-            "Could not read path: \"{}\". Defaulting to \"./\"",
-            path.to_str().unwrap()
+            "Could not read path: \"{}\". Defaulting to \"{}\"",
+            path.to_string_lossy(),
+            display_path(fallback)
         );
         Ok(ReadRes::FallBack {
             error: r,
-            files: read_path(&fallback).await?,
+            files: read_path(fallback).await?,
         })
     }
 }
@@ -65,3 +120,28 @@ pub async fn read_path(path: &PathBuf) -> Result<Vec<File>> {
     }
     Ok(files)
 }
+
+/// Adds up the size of everything below `path` into `total`, without following links.
+/// Directories that can not be read are skipped, it stops early once `cancel` is set
+pub fn dir_size(path: &Path, total: &AtomicU64, cancel: &AtomicBool) {
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
+            // the metadata of entries is not read through links
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                stack.push(entry.path());
+            } else {
+                total.fetch_add(metadata.len(), Ordering::Relaxed);
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::cmp::Ordering;
+
+use clap::ValueEnum;
+
+use super::read::File;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, ValueEnum)]
+pub enum SortBy {
+    #[default]
+    Name,
+    Size,
+    Modified,
+    Extension,
+}
+
+impl SortBy {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Name => Self::Size,
+            Self::Size => Self::Modified,
+            Self::Modified => Self::Extension,
+            Self::Extension => Self::Name,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Size => "size",
+            Self::Modified => "modified",
+            Self::Extension => "extension",
+        }
+    }
+}
+
+/// How a directory listing is sorted and filtered, shared by the ui and `--list`
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct ListOptions {
+    pub sort: SortBy,
+    pub reverse: bool,
+    pub show_hidden: bool,
+}
+
+impl ListOptions {
+    pub fn is_visible(&self, name: &str) -> bool {
+        self.show_hidden || !name.starts_with('.')
+    }
+
+    pub fn apply(&self, files: &mut Vec<File>) {
+        files.retain(|f| self.is_visible(&f.name.to_string_lossy()));
+        // checking for a directory follows links, so it is only done once per file
+        let mut keyed = files
+            .drain(..)
+            .map(|f| (f.is_dir().unwrap_or_default(), f))
+            .collect::<Vec<_>>();
+        keyed.sort_by(|(a_dir, a), (b_dir, b)| {
+            // directories always go first, no matter the order
+            let dirs = b_dir.cmp(a_dir);
+            let ord = match self.sort {
+                SortBy::Name => Ordering::Equal,
+                SortBy::Size => a.size.cmp(&b.size),
+                SortBy::Modified => a.modified.cmp(&b.modified),
+                SortBy::Extension => a.path.extension().cmp(&b.path.extension()),
+            }
+            .then_with(|| compare_names(a, b));
+            dirs.then(if self.reverse { ord.reverse() } else { ord })
+        });
+        files.extend(keyed.into_iter().map(|(_, f)| f));
+    }
+}
+
+fn compare_names(a: &File, b: &File) -> Ordering {
+    let (a, b) = (a.name.to_string_lossy(), b.name.to_string_lossy());
+    a.to_lowercase()
+        .cmp(&b.to_lowercase())
+        .then_with(|| a.cmp(&b))
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::filesystem::read::read_path;
+
+    #[tokio::test]
+    async fn sort_and_filter_tests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        fs::create_dir_all(dir.join("b_dir")).unwrap();
+        fs::write(dir.join("a.txt"), "1234").unwrap();
+        fs::write(dir.join("C.rs"), "12").unwrap();
+        fs::write(dir.join(".hidden"), "").unwrap();
+
+        let names = |opts: ListOptions, mut files: Vec<File>| {
+            opts.apply(&mut files);
+            files
+                .into_iter()
+                .map(|f| f.name.into_string().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let opts = ListOptions::default();
+        let files = read_path(&dir).await.unwrap();
+        assert_eq!(names(opts, files), ["b_dir", "a.txt", "C.rs"]);
+
+        let opts = ListOptions {
+            sort: SortBy::Size,
+            reverse: true,
+            show_hidden: true,
+        };
+        let files = read_path(&dir).await.unwrap();
+        assert_eq!(names(opts, files), ["b_dir", "a.txt", "C.rs", ".hidden"]);
+    }
+}
@@ -1,4 +1,6 @@
-use std::path::Path;
+use std::{fs::Metadata, path::Path};
+
+use super::read::FileKind;
 
 const FILE_EXTENTION_REGEX: &str = r"\.([0-9a-zA-Z]+)$";
 
@@ -8,6 +10,7 @@ pub enum FileType<'a> {
     Folder,
 }
 
+#[allow(mismatched_lifetime_syntaxes)]
 pub fn get_type_by_name(name: &str) -> FileType {
     if name.ends_with('\\') || name.ends_with('/') {
         FileType::Folder
@@ -21,6 +24,7 @@ pub fn get_type_by_name(name: &str) -> FileType {
     }
 }
 
+#[allow(mismatched_lifetime_syntaxes)]
 pub fn get_type_by_path(file: &Path) -> FileType {
     if file.is_dir() {
         FileType::Folder
@@ -29,6 +33,58 @@ pub fn get_type_by_path(file: &Path) -> FileType {
     }
 }
 
+/// The permission bits of a file, `None` where they are not available
+#[cfg(unix)]
+pub fn get_mode(metadata: &Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+pub fn get_mode(_metadata: &Metadata) -> Option<u32> {
+    None
+}
+
+/// Formats permissions like `ls -l` does, e.g. `drwxr-xr-x`
+pub fn format_mode(kind: FileKind, mode: Option<u32>, readonly: bool) -> String {
+    let kind = match kind {
+        FileKind::Symlink => 'l',
+        FileKind::Dir => 'd',
+        _ => '-',
+    };
+    let Some(mode) = mode else {
+        let write = if readonly { '-' } else { 'w' };
+        return format!("{kind}r{write}");
+    };
+
+    let mut s = String::from(kind);
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 0o7;
+        s.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        s.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        s.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    s
+}
+
+/// Human readable size, e.g. `1.5K`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+    let mut size = bytes as f64;
+    let mut unit = "";
+    for u in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = u;
+    }
+    format!("{size:.1}{unit}")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -46,4 +102,19 @@ mod test {
             assert_eq!(expected, get_type_by_name(path));
         }
     }
+
+    #[test]
+    fn format_size_tests() {
+        let sizes = [
+            (0, "0B"),
+            (1023, "1023B"),
+            (1024, "1.0K"),
+            (1536, "1.5K"),
+            (5 * 1024 * 1024, "5.0M"),
+        ];
+
+        for (size, expected) in sizes {
+            assert_eq!(expected, format_size(size));
+        }
+    }
 }
@@ -0,0 +1,204 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use eyre::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::data_path;
+
+const FRECENCY_FILE: &str = "frecency.json";
+// how many matches the jump prompt shows
+pub const JUMP_MATCHES: usize = 20;
+// once the ranks add up to more than this, every rank is aged
+const MAX_RANK: f64 = 10_000.0;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    path: PathBuf,
+    rank: f64,
+    // seconds since the unix epoch
+    last_accessed: u64,
+}
+
+impl Entry {
+    /// Frequency weighted by how recent the last visit was, the same way zoxide does it
+    fn score(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_accessed);
+        let multiplier = if age < HOUR {
+            4.0
+        } else if age < DAY {
+            2.0
+        } else if age < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        self.rank * multiplier
+    }
+}
+
+/// Every visited directory with how often and how recently it was visited
+#[derive(Default)]
+pub struct Frecency {
+    entries: Vec<Entry>,
+    file: Option<PathBuf>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl Frecency {
+    pub fn load() -> Result<Self> {
+        let file = data_path(FRECENCY_FILE);
+        let entries = match &file {
+            Some(file) if file.exists() => {
+                let content = fs::read_to_string(file).wrap_err_with(|| {
+                    format!(
+                        "Could not read the directory database: \"{}\"",
+                        file.display()
+                    )
+                })?;
+                serde_json::from_str(&content).wrap_err_with(|| {
+                    format!(
+                        "Could not parse the directory database: \"{}\"",
+                        file.display()
+                    )
+                })?
+            }
+            _ => Vec::default(),
+        };
+        Ok(Self { entries, file })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(file, serde_json::to_string(&self.entries)?).wrap_err_with(|| {
+            format!(
+                "Could not save the directory database: \"{}\"",
+                file.display()
+            )
+        })
+    }
+
+    /// Counts a visit of `path`, it is kept in memory until `save` is called
+    pub fn add(&mut self, path: &Path) {
+        self.add_at(path, now());
+    }
+
+    fn add_at(&mut self, path: &Path, now: u64) {
+        match self.entries.iter_mut().find(|e| e.path == path) {
+            Some(entry) => {
+                entry.rank += 1.0;
+                entry.last_accessed = now;
+            }
+            None => self.entries.push(Entry {
+                path: path.to_path_buf(),
+                rank: 1.0,
+                last_accessed: now,
+            }),
+        }
+
+        if self.entries.iter().map(|e| e.rank).sum::<f64>() > MAX_RANK {
+            for entry in self.entries.iter_mut() {
+                entry.rank *= 0.9;
+            }
+            self.entries.retain(|e| e.rank >= 1.0);
+        }
+    }
+
+    /// The directories that fuzzy match `query`, best first. Directories that no longer exist
+    /// are left out
+    pub fn query(&self, query: &str, limit: usize) -> Vec<&Path> {
+        self.query_at(query, now())
+            .into_iter()
+            .filter(|p| p.is_dir())
+            .take(limit)
+            .collect()
+    }
+
+    fn query_at(&self, query: &str, now: u64) -> Vec<&Path> {
+        let terms = query
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+
+        let mut matches = self
+            .entries
+            .iter()
+            .filter_map(|e| {
+                let path = e.path.to_string_lossy().to_lowercase();
+                let name = e
+                    .path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                if !terms.iter().all(|t| is_subsequence(t, &path)) {
+                    return None;
+                }
+                // prefer directories where the last term matches the name itself
+                let in_name = terms.last().is_none_or(|t| is_subsequence(t, &name));
+                Some((in_name, e.score(now), e.path.as_path()))
+            })
+            .collect::<Vec<_>>();
+
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.total_cmp(&a.1)));
+        matches.into_iter().map(|(_, _, p)| p).collect()
+    }
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn query_tests() {
+        let mut frecency = Frecency::default();
+        let old = 1_000_000;
+        let now = old + 2 * WEEK;
+        for _ in 0..20 {
+            frecency.add_at(Path::new("/home/user/projects/onyx"), old);
+        }
+        frecency.add_at(Path::new("/home/user/documents"), now);
+        frecency.add_at(Path::new("/home/user/onyx-notes/docs"), now);
+        frecency.add_at(Path::new("/tmp/onyx"), now);
+
+        // 20 visits two weeks ago still beat 1 visit now
+        assert_eq!(
+            frecency.query_at("onyx", now),
+            [
+                Path::new("/home/user/projects/onyx"),
+                Path::new("/tmp/onyx"),
+                Path::new("/home/user/onyx-notes/docs"),
+            ]
+        );
+        assert_eq!(
+            frecency.query_at("usr dcs", now),
+            [
+                Path::new("/home/user/documents"),
+                Path::new("/home/user/onyx-notes/docs")
+            ]
+        );
+        assert!(frecency.query_at("nothing", now).is_empty());
+    }
+}
@@ -0,0 +1,468 @@
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::{Duration, Instant, SystemTime},
+};
+
+use eyre::{eyre, Context, Result};
+
+use crate::filesystem::read::{File, FileKind};
+
+// the status is read again this often, changes deeper down do not show up in the listing
+const STATUS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The `.git` directory of the repository `path` is in. Worktrees and submodules have a `.git`
+/// file pointing to it instead
+fn git_dir(path: &Path) -> Option<PathBuf> {
+    path.ancestors().find_map(|dir| {
+        let git = dir.join(".git");
+        if git.is_dir() {
+            return Some(git);
+        }
+        let content = fs::read_to_string(&git).ok()?;
+        let target = content.strip_prefix("gitdir:")?.trim();
+        Some(dir.join(target))
+    })
+}
+
+/// The top directory of the repository `path` is in
+pub fn root(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|dir| dir.join(".git").exists())
+}
+
+/// The checked out branch, or the short commit hash when the head is detached
+pub fn branch(path: &Path) -> Option<String> {
+    let head = fs::read_to_string(git_dir(path)?.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref:") {
+        Some(name) => {
+            let name = name.trim();
+            Some(name.strip_prefix("refs/heads/").unwrap_or(name).to_string())
+        }
+        None => Some(head.chars().take(7).collect()),
+    }
+}
+
+/// Runs git in `dir`, what it complains about becomes the error
+fn git(dir: &Path, args: impl IntoIterator<Item = impl AsRef<OsStr>>) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .wrap_err("Could not run git")?;
+    if !output.status.success() {
+        return Err(eyre!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(output.stdout)
+}
+
+// runs a git command on `files`, from the directory they are in
+fn on_files(dir: &Path, args: &[&str], files: &[PathBuf]) -> Result<()> {
+    let args = args
+        .iter()
+        .map(OsStr::new)
+        .chain([OsStr::new("--")])
+        .chain(files.iter().map(|f| f.as_os_str()));
+    git(dir, args)?;
+    Ok(())
+}
+
+/// Adds the changes of `files` to the index
+pub fn stage(dir: &Path, files: &[PathBuf]) -> Result<()> {
+    on_files(dir, &["add", "--all"], files).wrap_err("Could not stage")
+}
+
+/// Takes the changes of `files` out of the index again, they stay in the working tree
+pub fn unstage(dir: &Path, files: &[PathBuf]) -> Result<()> {
+    on_files(dir, &["reset", "--quiet"], files).wrap_err("Could not unstage")
+}
+
+/// Throws away the changes of `files` that are not staged
+pub fn discard(dir: &Path, files: &[PathBuf]) -> Result<()> {
+    on_files(dir, &["restore", "--worktree"], files).wrap_err("Could not discard changes")
+}
+
+/// The changes of `path` since the last commit, as a unified diff
+pub fn diff(path: &Path) -> Result<String> {
+    let dir = path.parent().unwrap_or(path);
+    let diff = |against: &str| {
+        let args = ["diff", "--no-color", "--no-ext-diff", against, "--"];
+        git(
+            dir,
+            args.map(OsStr::new).into_iter().chain([path.as_os_str()]),
+        )
+    };
+    // there is no last commit before the first one, the staged changes are shown then
+    let output = diff("HEAD")
+        .or_else(|_| diff("--cached"))
+        .wrap_err("Could not diff")?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// A line of a file and the commit that last changed it
+#[derive(Default, PartialEq, Debug)]
+pub struct BlameLine {
+    pub hash: String,
+    pub author: String,
+    // seconds since the epoch
+    pub time: i64,
+    pub text: String,
+}
+
+fn parse_blame(output: &str) -> Vec<BlameLine> {
+    let mut lines = vec![];
+    let mut line = BlameLine::default();
+    for row in output.lines() {
+        if let Some(text) = row.strip_prefix('\t') {
+            line.text = text.to_string();
+            lines.push(std::mem::take(&mut line));
+        } else if let Some(author) = row.strip_prefix("author ") {
+            line.author = author.to_string();
+        } else if let Some(time) = row.strip_prefix("author-time ") {
+            line.time = time.parse().unwrap_or_default();
+        } else {
+            // each line starts with the full hash of its commit
+            let first = row.split(' ').next().unwrap_or_default();
+            if first.len() >= 40 && first.bytes().all(|b| b.is_ascii_hexdigit()) {
+                line.hash = first[..7].to_string();
+            }
+        }
+    }
+    lines
+}
+
+/// Who last changed each line of `path`, changes that are not committed yet included
+pub fn blame(path: &Path) -> Result<Vec<BlameLine>> {
+    let dir = path.parent().unwrap_or(path);
+    let output = git(
+        dir,
+        [
+            OsStr::new("blame"),
+            OsStr::new("--line-porcelain"),
+            OsStr::new("--"),
+            path.as_os_str(),
+        ],
+    )
+    .wrap_err("Could not blame")?;
+    Ok(parse_blame(&String::from_utf8_lossy(&output)))
+}
+
+/// How a file differs from what git knows about it, later ones matter more
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum FileStatus {
+    Ignored,
+    Untracked,
+    Staged,
+    Modified,
+    Conflicted,
+}
+
+impl FileStatus {
+    /// Parses the two status letters of `git status --porcelain`
+    fn parse(x: u8, y: u8) -> Self {
+        match (x, y) {
+            (b'!', b'!') => Self::Ignored,
+            (b'?', b'?') => Self::Untracked,
+            (b'U', _) | (_, b'U') | (b'A', b'A') | (b'D', b'D') => Self::Conflicted,
+            (_, b' ') => Self::Staged,
+            _ => Self::Modified,
+        }
+    }
+
+    pub fn marker(self) -> char {
+        match self {
+            Self::Ignored => '·',
+            Self::Untracked => '?',
+            Self::Staged => '+',
+            Self::Modified => '~',
+            Self::Conflicted => '!',
+        }
+    }
+}
+
+/// The status of every changed file of a repository, by absolute path
+#[derive(Default, Debug)]
+pub struct RepoStatus {
+    pub root: PathBuf,
+    files: HashMap<PathBuf, FileStatus>,
+    // the most important status of anything inside
+    dirs: HashMap<PathBuf, FileStatus>,
+}
+
+impl RepoStatus {
+    /// Parses the output of `git status --porcelain -z`
+    fn parse(root: &Path, output: &[u8]) -> Self {
+        let mut status = Self {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+        let mut entries = output.split(|b| *b == 0);
+        while let Some(entry) = entries.next() {
+            let [x, y, b' ', path @ ..] = entry else {
+                continue;
+            };
+            // renames are followed by where they came from
+            if matches!(x, b'R' | b'C') {
+                entries.next();
+            }
+            let file_status = FileStatus::parse(*x, *y);
+            // untracked and ignored directories end with a slash
+            let path = String::from_utf8_lossy(path);
+            let path = root.join(path.trim_end_matches('/'));
+            // having ignored files inside is nothing worth showing
+            if file_status != FileStatus::Ignored {
+                for dir in path.ancestors().skip(1).take_while(|d| d.starts_with(root)) {
+                    let current = status.dirs.entry(dir.to_path_buf()).or_insert(file_status);
+                    *current = (*current).max(file_status);
+                }
+            }
+            status.files.insert(path, file_status);
+        }
+        status
+    }
+
+    /// Reads the status of the repository at `root` with the `git` command
+    pub fn read(root: &Path) -> Result<Self> {
+        let output = git(
+            root,
+            [
+                "--no-optional-locks",
+                "status",
+                "--porcelain=v1",
+                "-z",
+                "--ignored",
+            ],
+        )
+        .wrap_err("git status failed")?;
+        Ok(Self::parse(root, &output))
+    }
+
+    /// The status of `file`, directories get the one of their contents
+    pub fn get(&self, file: &File) -> Option<FileStatus> {
+        if let Some(status) = self.files.get(&file.path) {
+            return Some(*status);
+        }
+        // the files inside untracked or ignored directories are not listed one by one
+        let inherited = file
+            .path
+            .ancestors()
+            .skip(1)
+            .take_while(|d| d.starts_with(&self.root))
+            .find_map(|d| self.files.get(d))
+            .filter(|s| matches!(s, FileStatus::Untracked | FileStatus::Ignored));
+        match inherited {
+            Some(status) => Some(*status),
+            None if file.kind == FileKind::Dir => self.dirs.get(&file.path).copied(),
+            None => None,
+        }
+    }
+}
+
+// once any of it changes, the status is read again
+#[derive(PartialEq)]
+struct StatusKey {
+    root: PathBuf,
+    index: Option<SystemTime>,
+    newest: Option<SystemTime>,
+}
+
+/// Keeps the status of the repository a tab is in, reading it in the background
+#[derive(Default)]
+pub struct StatusTracker {
+    pub status: Option<RepoStatus>,
+    key: Option<StatusKey>,
+    read_at: Option<Instant>,
+    // git is not tried again until something changes, e.g. when it is not installed
+    failed: bool,
+    pending: Option<Receiver<Result<RepoStatus>>>,
+}
+
+impl StatusTracker {
+    /// Picks up a finished read, and starts another one once `path` or the `files` listed in it
+    /// changed. Errors from reads are returned
+    pub fn update(&mut self, path: &Path, files: &[File]) -> Result<()> {
+        if let Some(receiver) = &self.pending {
+            match receiver.try_recv() {
+                Ok(status) => {
+                    self.pending = None;
+                    self.failed = status.is_err();
+                    self.status = Some(status?);
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.pending = None,
+            }
+        }
+
+        let Some(root) = root(path) else {
+            *self = Self::default();
+            return Ok(());
+        };
+        let key = StatusKey {
+            root: root.to_path_buf(),
+            index: git_dir(root)
+                .and_then(|d| fs::metadata(d.join("index")).ok())
+                .and_then(|m| m.modified().ok()),
+            newest: files.iter().filter_map(|f| f.modified).max(),
+        };
+        let stale = self.key.as_ref() != Some(&key)
+            || (!self.failed && self.read_at.is_none_or(|t| t.elapsed() >= STATUS_INTERVAL));
+        if !stale || self.pending.is_some() {
+            return Ok(());
+        }
+        if self.key.as_ref().is_some_and(|k| k.root != key.root) {
+            self.status = None;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let task_root = key.root.clone();
+        tokio::task::spawn_blocking(move || {
+            let _ = sender.send(RepoStatus::read(&task_root));
+        });
+        self.pending = Some(receiver);
+        self.key = Some(key);
+        self.read_at = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Reads the status again with the next update, e.g. after staging files
+    pub fn invalidate(&mut self) {
+        self.read_at = None;
+        self.failed = false;
+    }
+
+    pub fn get(&self, file: &File) -> Option<FileStatus> {
+        self.status.as_ref()?.get(file)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn branch_tests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("repo/.git")).unwrap();
+        fs::create_dir_all(dir.join("repo/src/deep")).unwrap();
+        fs::create_dir_all(dir.join("worktree")).unwrap();
+
+        fs::write(dir.join("repo/.git/HEAD"), "ref: refs/heads/feature/x\n").unwrap();
+        assert_eq!(branch(&dir.join("repo/src/deep")).unwrap(), "feature/x");
+
+        fs::write(dir.join("repo/.git/HEAD"), "0123456789abcdef\n").unwrap();
+        assert_eq!(branch(&dir.join("repo")).unwrap(), "0123456");
+
+        fs::write(dir.join("worktree/.git"), "gitdir: ../repo/.git\n").unwrap();
+        assert_eq!(branch(&dir.join("worktree")).unwrap(), "0123456");
+    }
+
+    #[test]
+    fn status_tests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("src/deep")).unwrap();
+        fs::create_dir_all(dir.join("new")).unwrap();
+        fs::create_dir_all(dir.join("target")).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(dir)
+                .args(args)
+                .stdout(Stdio::null())
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+        fs::write(dir.join("src/staged.txt"), "a").unwrap();
+        fs::write(dir.join("src/deep/changed.txt"), "a").unwrap();
+        fs::write(dir.join("new/a.txt"), "a").unwrap();
+        fs::write(dir.join("target/out"), "a").unwrap();
+        git(&["add", "src"]);
+        fs::write(dir.join("src/deep/changed.txt"), "b").unwrap();
+
+        let status = RepoStatus::read(dir).unwrap();
+        let get = |path: &str, kind| {
+            let path = dir.join(path);
+            let file = File {
+                name: path.file_name().unwrap().to_os_string(),
+                path,
+                kind,
+                size: 0,
+                modified: None,
+                mode: None,
+                readonly: false,
+            };
+            status.get(&file)
+        };
+        assert_eq!(
+            get("src/staged.txt", FileKind::File),
+            Some(FileStatus::Staged)
+        );
+        assert_eq!(
+            get("src/deep/changed.txt", FileKind::File),
+            Some(FileStatus::Modified)
+        );
+        assert_eq!(get("src", FileKind::Dir), Some(FileStatus::Modified));
+        assert_eq!(
+            get("new/a.txt", FileKind::File),
+            Some(FileStatus::Untracked)
+        );
+        assert_eq!(get("new", FileKind::Dir), Some(FileStatus::Untracked));
+        assert_eq!(get("target/out", FileKind::File), Some(FileStatus::Ignored));
+        assert_eq!(
+            get(".gitignore", FileKind::File),
+            Some(FileStatus::Untracked)
+        );
+        assert_eq!(root(&dir.join("src/deep")), Some(dir));
+    }
+
+    #[test]
+    fn actions_tests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let files = [dir.join("a.txt")];
+        let file = &files[0];
+        let run = |args: &[&str]| git(dir, args).unwrap();
+        run(&["init", "-q"]);
+        fs::write(file, "one\n").unwrap();
+
+        // before the first commit
+        stage(dir, &files).unwrap();
+        assert_eq!(run(&["diff", "--cached", "--name-only"]), b"a.txt\n");
+        unstage(dir, &files).unwrap();
+        assert!(run(&["diff", "--cached", "--name-only"]).is_empty());
+
+        stage(dir, &files).unwrap();
+        run(&[
+            "-c",
+            "user.name=Someone",
+            "-c",
+            "user.email=someone@example.com",
+            "commit",
+            "-qm",
+            "first",
+        ]);
+        fs::write(file, "one\ntwo\n").unwrap();
+        assert!(diff(file).unwrap().contains("\n+two\n"));
+
+        let blame = blame(file).unwrap();
+        assert_eq!(blame.len(), 2);
+        assert_eq!(blame[0].author, "Someone");
+        assert_eq!(blame[0].text, "one");
+        assert_eq!(blame[0].hash.len(), 7);
+        assert_eq!(blame[1].author, "Not Committed Yet");
+
+        discard(dir, &files).unwrap();
+        assert_eq!(fs::read_to_string(file).unwrap(), "one\n");
+        assert!(diff(file).unwrap().is_empty());
+    }
+}
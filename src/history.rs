@@ -0,0 +1,72 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+
+const MAX_RECENT: usize = 100;
+
+/// Browser like back and forward history, one for each tab
+#[derive(Default)]
+pub struct History {
+    stack: Vec<PathBuf>,
+    position: usize,
+    // most recent first
+    pub recent: Vec<(PathBuf, DateTime<Local>)>,
+}
+
+impl History {
+    /// Records a path change, going back or forward lands on the current entry so nothing is
+    /// added to the stack
+    pub fn visit(&mut self, path: &Path) {
+        if self.stack.get(self.position).map(PathBuf::as_path) != Some(path) {
+            self.stack.truncate(self.position + 1);
+            self.stack.push(path.to_path_buf());
+            self.position = self.stack.len() - 1;
+        }
+
+        self.recent.retain(|(p, _)| p != path);
+        self.recent.insert(0, (path.to_path_buf(), Local::now()));
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    pub fn back(&mut self) -> Option<PathBuf> {
+        self.position = self.position.checked_sub(1)?;
+        self.stack.get(self.position).cloned()
+    }
+
+    pub fn forward(&mut self) -> Option<PathBuf> {
+        if self.position + 1 >= self.stack.len() {
+            return None;
+        }
+        self.position += 1;
+        self.stack.get(self.position).cloned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn back_forward_tests() {
+        let mut history = History::default();
+        for path in ["/a", "/b", "/c"] {
+            history.visit(Path::new(path));
+        }
+
+        assert_eq!(history.back(), Some(PathBuf::from("/b")));
+        history.visit(Path::new("/b"));
+        assert_eq!(history.back(), Some(PathBuf::from("/a")));
+        history.visit(Path::new("/a"));
+        assert_eq!(history.back(), None);
+        assert_eq!(history.forward(), Some(PathBuf::from("/b")));
+        history.visit(Path::new("/b"));
+
+        // a new path drops everything after the current one
+        history.visit(Path::new("/d"));
+        assert_eq!(history.forward(), None);
+        assert_eq!(history.back(), Some(PathBuf::from("/b")));
+
+        let recent = history.recent.iter().map(|(p, _)| p.to_str().unwrap());
+        assert_eq!(recent.collect::<Vec<_>>(), ["/d", "/b", "/a", "/c"]);
+    }
+}
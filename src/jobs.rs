@@ -0,0 +1,79 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+};
+
+use eyre::{eyre, Result};
+
+use crate::filesystem::modify::{self, Progress};
+
+/// Work running in the background, like packing or unpacking archives
+pub struct Job {
+    pub name: String,
+    pub progress: Arc<Progress>,
+    // what to tell once it is done
+    receiver: Receiver<Result<String>>,
+}
+
+impl Job {
+    pub fn spawn(
+        name: String,
+        work: impl FnOnce(&Progress) -> Result<String> + Send + 'static,
+    ) -> Self {
+        let progress = Arc::new(Progress::default());
+        let (sender, receiver) = mpsc::channel();
+        let task_progress = progress.clone();
+        tokio::task::spawn_blocking(move || {
+            // the causes are shown on the same line, the info bar only has one
+            let _ = sender.send(work(&task_progress).map_err(|e| eyre!("{e:#}")));
+        });
+        Self {
+            name,
+            progress,
+            receiver,
+        }
+    }
+
+    /// Packs `files` into the new archive `dest`
+    pub fn compress(files: Vec<PathBuf>, dest: PathBuf) -> Self {
+        let name = dest
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        Self::spawn(format!("Compressing {name}"), move |progress| {
+            modify::compress(&files, &dest, progress)?;
+            Ok(format!("Created {name}"))
+        })
+    }
+
+    /// Unpacks the archive `file` into `dest`
+    pub fn extract(file: PathBuf, dest: PathBuf) -> Self {
+        let name = file
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        Self::spawn(
+            format!("Extracting {name}"),
+            move |progress| match modify::extract(&file, &dest, progress)? {
+                0 => Ok(format!("Extracted {name}")),
+                skipped => Ok(format!(
+                    "Extracted {name}, skipped {skipped} entries pointing outside of it"
+                )),
+            },
+        )
+    }
+
+    /// The outcome once the job is done, `None` while it is still running
+    pub fn poll(&self) -> Option<Result<String>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(eyre!("{} stopped", self.name))),
+        }
+    }
+}
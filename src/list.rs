@@ -0,0 +1,158 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use eyre::{Context, Result};
+use serde::Serialize;
+
+use crate::filesystem::{
+    read::{read_path, File},
+    sort::ListOptions,
+    utils::{format_mode, format_size},
+};
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum ListFormat {
+    #[default]
+    Plain,
+    Long,
+    Json,
+}
+
+#[derive(Serialize)]
+struct Entry {
+    name: String,
+    path: PathBuf,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    size: u64,
+    // seconds since the unix epoch
+    mtime: Option<u64>,
+    // octal permission bits, e.g. "0644"
+    mode: Option<String>,
+    symlink_target: Option<PathBuf>,
+}
+
+impl Entry {
+    fn new(file: &File) -> Self {
+        Self {
+            name: file.name.to_string_lossy().to_string(),
+            path: file.path.clone(),
+            kind: file.kind_name(),
+            size: file.size,
+            mtime: file
+                .modified
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+            mode: file.mode.map(|m| format!("{m:04o}")),
+            symlink_target: fs::read_link(&file.path).ok(),
+        }
+    }
+}
+
+/// Prints a directory the same way the ui would show it, without starting the ui
+pub async fn list(path: &Path, opts: &ListOptions, format: ListFormat) -> Result<()> {
+    write_list(path, opts, format, &mut io::stdout().lock()).await
+}
+
+async fn write_list(
+    path: &Path,
+    opts: &ListOptions,
+    format: ListFormat,
+    out: &mut impl Write,
+) -> Result<()> {
+    let path = path
+        .canonicalize()
+        .wrap_err_with(|| format!("Could not read path: \"{}\".", path.display()))?;
+    let mut files = read_path(&path).await?;
+    opts.apply(&mut files);
+
+    match format {
+        ListFormat::Plain => {
+            for file in &files {
+                writeln!(out, "{}", file.name.to_string_lossy())?;
+            }
+        }
+        ListFormat::Long => {
+            for file in &files {
+                let modified = file
+                    .modified
+                    .map(|t| {
+                        DateTime::<Local>::from(t)
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string()
+                    })
+                    .unwrap_or_else(|| "-".repeat(16));
+                let mut line = format!(
+                    "{} {:>7} {} {}",
+                    format_mode(file.kind, file.mode, file.readonly),
+                    format_size(file.size),
+                    modified,
+                    file.name.to_string_lossy()
+                );
+                if let Ok(target) = fs::read_link(&file.path) {
+                    line.push_str(&format!(" -> {}", target.display()));
+                }
+                writeln!(out, "{line}")?;
+            }
+        }
+        ListFormat::Json => {
+            let entries = files.iter().map(Entry::new).collect::<Vec<_>>();
+            serde_json::to_writer_pretty(&mut *out, &entries)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use clap::Parser;
+
+    use super::*;
+    use crate::{frecency::Frecency, settings::Settings, state::State, App};
+
+    #[tokio::test]
+    async fn same_order_as_ui_tests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().canonicalize().unwrap();
+        fs::create_dir_all(dir.join("b_dir")).unwrap();
+        fs::write(dir.join("a.txt"), "1234").unwrap();
+        fs::write(dir.join("C.rs"), "12").unwrap();
+        fs::write(dir.join(".hidden"), "").unwrap();
+
+        for args in [
+            &["ox"][..],
+            &["ox", "--all", "--sort", "size"],
+            &["ox", "--reverse"],
+        ] {
+            let opts = Settings::parse_from(args).list_options();
+            let mut out = Vec::new();
+            write_list(&dir, &opts, ListFormat::Plain, &mut out)
+                .await
+                .unwrap();
+
+            let mut state = State {
+                path: dir.clone(),
+                list: opts,
+                ..Default::default()
+            };
+            App::refresh(&mut state, false, &mut Frecency::default())
+                .await
+                .unwrap();
+            let shown = state
+                .files
+                .iter()
+                .map(|f| format!("{}\n", f.name.to_string_lossy()))
+                .collect::<String>();
+            assert_eq!(String::from_utf8(out).unwrap(), shown, "{args:?}");
+        }
+    }
+}
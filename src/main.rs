@@ -1,89 +1,594 @@
-use std::{path::PathBuf, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
+use bookmarks::Bookmarks;
 use crossterm::event;
-use eyre::Result;
-use filesystem::read::{read_path, read_with_fallback, ReadRes};
-use ratatui::widgets::ListState;
+use encoding_rs::Encoding;
+use eyre::{eyre, Result};
+use filesystem::{
+    archive::{self, ArchiveKind},
+    preview::{parse_offset, PreviewOptions},
+    previewer::Previewers,
+    read::{read_path, read_with_fallback, ReadRes},
+    sort::ListOptions,
+};
+use frecency::{Frecency, JUMP_MATCHES};
+use jobs::Job;
+use pick::Picker;
 use settings::parse_args;
-use state::{Info, InfoKind, Mode, State};
+use state::{Info, InfoKind, Mode, PaneLayout, Panes, State};
 
-use crate::ui::input::{InputModeResult, InputResult};
+use crate::ui::{
+    graphics::ImageProtocol,
+    input::{BookmarkAction, GitAction, InputModeResult, InputResult, PaneAction, TabAction},
+    mouse::SCROLL_STEP,
+    DrawContext,
+};
 
+mod bookmarks;
 mod filesystem;
+mod frecency;
+mod git;
+mod history;
+mod jobs;
+mod list;
+mod pick;
 mod settings;
+mod shell;
 mod state;
 mod ui;
 
 pub struct App {
     pub ui: ui::UiState,
-    pub state: State,
+    pub tabs: Vec<State>,
+    pub panes: Panes,
+    pub pick: Option<Picker>,
+    pub bookmarks: Bookmarks,
+    pub frecency: Frecency,
+    pub jobs: Vec<Job>,
 }
 
 impl App {
-    pub fn new(path: PathBuf) -> Result<Self> {
-        let files = Vec::default();
-        let ui_state = ui::UiState {
-            scroll_state: ListState::default(),
-        };
+    pub fn new(
+        paths: &[PathBuf],
+        list: ListOptions,
+        preview: PreviewOptions,
+        previewers: Previewers,
+        images: ImageProtocol,
+    ) -> Result<Self> {
+        let mut ui_state = ui::UiState::default();
+        ui_state.preview.options = preview;
+        ui_state.preview.previewers = Arc::new(previewers);
+        ui_state.preview.protocol = images.detect();
 
-        let state = State {
-            files,
-            info: Vec::default(),
-            path,
-            last_path: PathBuf::new(),
-            ..Default::default()
-        };
+        let mut tabs: Vec<State> = paths
+            .iter()
+            .map(|path| State {
+                files: Vec::default(),
+                info: Vec::default(),
+                // resolved here so tabs that are not visited yet still get a name
+                path: path.canonicalize().unwrap_or_else(|_| path.clone()),
+                last_path: PathBuf::new(),
+                list,
+                ..Default::default()
+            })
+            .collect();
+
+        let bookmarks = Bookmarks::load().unwrap_or_else(|e| {
+            tabs[0].info.push(Info::new(InfoKind::Error(e)));
+            Bookmarks::default()
+        });
+        let frecency = Frecency::load().unwrap_or_else(|e| {
+            tabs[0].info.push(Info::new(InfoKind::Error(e)));
+            Frecency::default()
+        });
         Ok(Self {
             ui: ui_state,
-            state,
+            tabs,
+            panes: Panes::default(),
+            pick: None,
+            bookmarks,
+            frecency,
+            jobs: Vec::new(),
         })
     }
 
-    pub async fn run(&mut self) -> Result<()> {
-        let mut terminal = ui::make_terminal()?;
+    pub fn state(&self) -> &State {
+        &self.tabs[self.panes.current]
+    }
+
+    pub async fn run(&mut self, keep_stdout: bool) -> Result<()> {
+        let mut terminal = ui::make_terminal(keep_stdout)?;
 
         loop {
-            let state = &mut self.state;
-            if state.last_path != state.path {
-                state.files = match read_with_fallback(&state.path, PathBuf::from("./")).await? {
-                    ReadRes::Read(files) => files,
-                    ReadRes::FallBack { error, files } => {
-                        state.path = PathBuf::from("./");
-                        state.info.push(Info::new(InfoKind::Error(error)));
-                        files
-                    }
-                };
-                if !state.path.is_absolute() {
-                    state.path = state.path.canonicalize()?;
-                }
-                state.last_path = state.path.clone()
-            } else {
-                state.files = read_path(&state.path).await?;
+            let miller = self.panes.layout == PaneLayout::Miller;
+            let frecency = &mut self.frecency;
+            App::refresh(&mut self.tabs[self.panes.current], miller, frecency).await?;
+            if self.panes.layout == PaneLayout::Dual {
+                App::refresh(&mut self.tabs[self.panes.other], false, frecency).await?;
             }
 
-            state.selected = state.selected.clamp(0, state.files.len().saturating_sub(1));
-            terminal.draw(|f| self.ui.draw(f, state))?;
-            State::purge_info(&mut state.info, Duration::from_secs(4)).await;
+            self.poll_jobs();
+
+            let ctx = DrawContext {
+                tabs: &self.tabs,
+                panes: &self.panes,
+                pick: self.pick.as_ref(),
+                bookmarks: &self.bookmarks,
+                frecency: &self.frecency,
+                jobs: &self.jobs,
+            };
+            terminal.draw(|f| self.ui.draw(f, &ctx))?;
+            if self.ui.preview.needs_clear() {
+                terminal.clear()?;
+                terminal.draw(|f| self.ui.draw(f, &ctx))?;
+            }
+            self.ui.preview.show_graphic(terminal.backend_mut())?;
+            for state in self.tabs.iter_mut() {
+                State::purge_info(&mut state.info, Duration::from_secs(4)).await;
+            }
 
             let event_ready =
                 tokio::task::spawn_blocking(|| event::poll(Duration::from_millis(250)));
 
-            if event_ready.await??
-                && App::handle_input(self.ui.input(event::read()?, state).await, state).await?
-            {
-                break;
+            if event_ready.await?? {
+                let state = &self.tabs[self.panes.current];
+                let input = self.ui.input(event::read()?, state).await;
+                if self.handle_input(input).await? {
+                    break;
+                }
             }
         }
 
         // restore terminal
+        self.ui.preview.graphic = None;
+        self.ui.preview.show_graphic(terminal.backend_mut())?;
         ui::restore_terminal(terminal)
     }
 
-    async fn handle_input(input: InputResult, state: &mut State) -> Result<bool> {
+    async fn refresh(state: &mut State, parent: bool, frecency: &mut Frecency) -> Result<()> {
+        let mut changed = state.last_path != state.path;
+        // the selection follows the file by name, so it survives re-sorting and new files
+        let selected_name = match state.pending_select.take() {
+            Some(name) => Some(name),
+            None if changed => None,
+            None => state.files.get(state.selected).map(|f| f.name.clone()),
+        };
+
+        if changed {
+            // stay where we are when the new path can not be read, e.g. a stale bookmark
+            let fallback = if state.last_path.is_dir() {
+                state.last_path.clone()
+            } else {
+                PathBuf::from("./")
+            };
+            let read = match archive::split(&state.path) {
+                Some(_) => {
+                    match archive::read_path(&state.path, &mut state.archive, &mut state.opening)
+                        .await
+                    {
+                        Ok(Some(files)) => ReadRes::Read(files),
+                        // still being read, the path counts as changed until it is
+                        Ok(None) => {
+                            state.files = Vec::new();
+                            state.selected = 0;
+                            state.pending_select = selected_name;
+                            return Ok(());
+                        }
+                        Err(error) => ReadRes::FallBack {
+                            error,
+                            files: read_path(&fallback).await?,
+                        },
+                    }
+                }
+                None => read_with_fallback(&state.path, &fallback).await?,
+            };
+            state.files = match read {
+                ReadRes::Read(files) => files,
+                ReadRes::FallBack { error, files } => {
+                    state.path = fallback;
+                    state.info.push(Info::new(InfoKind::Error(error)));
+                    files
+                }
+            };
+            if !state.path.is_absolute() {
+                state.path = state.path.canonicalize()?;
+            }
+            state.last_path = state.path.clone();
+            state.history.visit(&state.path);
+            if !state.in_archive() {
+                state.archive = None;
+                state.opening = None;
+                frecency.add(&state.path);
+            }
+        } else {
+            match archive::read_path(&state.path, &mut state.archive, &mut state.opening).await {
+                Ok(Some(files)) => state.files = files,
+                // changed on disk, the old listing is kept until it is read again
+                Ok(None) => {}
+                // removed or renamed from outside, what is left of it is shown instead
+                Err(error) => {
+                    state.info.push(Info::new(InfoKind::Error(error)));
+                    state.path = state
+                        .path
+                        .ancestors()
+                        .find(|p| p.is_dir())
+                        .map_or_else(|| PathBuf::from("./"), Path::to_path_buf);
+                    state.files = read_path(&state.path).await?;
+                    state.last_path = state.path.clone();
+                    state.history.visit(&state.path);
+                    state.archive = None;
+                    state.opening = None;
+                    changed = true;
+                }
+            }
+        }
+        state.list.apply(&mut state.files);
+
+        let selected_name = match selected_name {
+            None if changed => state.remembered.get(&state.path).cloned(),
+            name => name,
+        };
+        if changed {
+            state.selected = 0;
+        }
+        if let Some(name) = selected_name {
+            if let Some(pos) = state.files.iter().position(|f| f.name == name) {
+                state.selected = pos;
+            }
+        }
+        state.selected = state.selected.clamp(0, state.files.len().saturating_sub(1));
+        if let Some(file) = state.files.get(state.selected) {
+            state
+                .remembered
+                .insert(state.path.clone(), file.name.clone());
+        }
+
+        // only looked up again once the directory changes
+        if changed {
+            state.branch = git::branch(&state.path);
+            state.free_space = fs2::available_space(&state.path).ok();
+        }
+        if !state.in_archive() {
+            if let Err(e) = state.git.update(&state.path, &state.files) {
+                state.info.push(Info::new(InfoKind::Error(e)));
+            }
+        }
+
+        match state.path.parent() {
+            Some(path) if parent => {
+                // only read again once it or the options changed, members of archives have no time to check
+                let modified = tokio::fs::metadata(path)
+                    .await
+                    .and_then(|m| m.modified())
+                    .ok();
+                let read = modified.map(|m| (path.to_path_buf(), m, state.list));
+                if read.is_none() || read != state.parent_read {
+                    let files =
+                        archive::read_path(path, &mut state.archive, &mut state.opening).await;
+                    state.parent_read = read.filter(|_| matches!(files, Ok(Some(_))));
+                    state.parent_files = files.ok().flatten().unwrap_or_default();
+                    state.list.apply(&mut state.parent_files);
+                }
+            }
+            _ => {
+                state.parent_files = Vec::default();
+                state.parent_read = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_tab(&mut self, action: TabAction) {
+        let len = self.tabs.len();
+        let panes = &mut self.panes;
+        let previous = panes.current;
+        match action {
+            TabAction::New => {
+                let state = &self.tabs[panes.current];
+                let tab = State {
+                    path: state.path.clone(),
+                    list: state.list,
+                    ..Default::default()
+                };
+                self.tabs.insert(panes.current + 1, tab);
+                if panes.other > panes.current {
+                    panes.other += 1;
+                }
+                panes.current += 1;
+            }
+            TabAction::Close if len == 1 => {
+                self.tabs[panes.current]
+                    .info
+                    .push(Info::new(InfoKind::Message(
+                        "Can not close the last tab".to_string(),
+                    )));
+            }
+            TabAction::Close => {
+                self.tabs.remove(panes.current);
+                if panes.other > panes.current {
+                    panes.other -= 1;
+                }
+                panes.current = panes.current.min(self.tabs.len() - 1);
+            }
+            TabAction::Next => panes.current = (panes.current + 1) % len,
+            TabAction::Prev => panes.current = (panes.current + len - 1) % len,
+            TabAction::MoveLeft if panes.current > 0 => {
+                self.tabs.swap(panes.current, panes.current - 1);
+                if panes.other == panes.current - 1 {
+                    panes.other = panes.current;
+                }
+                panes.current -= 1;
+            }
+            TabAction::Select(i) if i < len => panes.current = i,
+            TabAction::MoveRight if panes.current + 1 < len => {
+                self.tabs.swap(panes.current, panes.current + 1);
+                if panes.other == panes.current + 1 {
+                    panes.other = panes.current;
+                }
+                panes.current += 1;
+            }
+            _ => {}
+        }
+
+        // switching onto the inactive pane swaps the panes
+        if panes.other == panes.current && previous < self.tabs.len() {
+            panes.other = previous;
+        }
+        self.fix_panes();
+    }
+
+    /// Makes sure the inactive pane points at a different tab, falling back to a single pane
+    fn fix_panes(&mut self) {
+        let panes = &mut self.panes;
+        if panes.layout != PaneLayout::Dual {
+            return;
+        }
+        if self.tabs.len() == 1 {
+            panes.layout = PaneLayout::Single;
+        } else if panes.other >= self.tabs.len() || panes.other == panes.current {
+            panes.other = (panes.current + 1) % self.tabs.len();
+        }
+    }
+
+    fn handle_pane(&mut self, action: PaneAction) {
+        match action {
+            PaneAction::CycleLayout => {
+                self.panes.layout = self.panes.layout.next();
+                if self.panes.layout == PaneLayout::Dual && self.tabs.len() == 1 {
+                    self.handle_tab(TabAction::New);
+                    self.panes.current = 0;
+                    self.panes.other = 1;
+                }
+                self.fix_panes();
+            }
+            PaneAction::SwapFocus if self.panes.layout == PaneLayout::Dual => {
+                core::mem::swap(&mut self.panes.current, &mut self.panes.other);
+            }
+            PaneAction::Sync if self.panes.layout == PaneLayout::Dual => {
+                let path = self.state().path.clone();
+                let other = &mut self.tabs[self.panes.other];
+                other.path = path;
+            }
+            _ => {}
+        }
+    }
+
+    /// Tells about jobs that are done and forgets them
+    fn poll_jobs(&mut self) {
+        let info = &mut self.tabs[self.panes.current].info;
+        self.jobs.retain(|job| match job.poll() {
+            None => true,
+            Some(result) => {
+                info.push(Info::new(match result {
+                    Ok(msg) => InfoKind::Message(msg),
+                    Err(e) => InfoKind::Error(e),
+                }));
+                false
+            }
+        });
+    }
+
+    /// The marked files, or the selected one when nothing is marked
+    fn selection(state: &State) -> Vec<PathBuf> {
+        let mut files = state.marked.iter().cloned().collect::<Vec<_>>();
+        files.sort();
+        if files.is_empty() {
+            files.extend(state.files.get(state.selected).map(|f| f.path.clone()));
+        }
+        files
+    }
+
+    fn start_compress(&mut self) {
+        let state = &mut self.tabs[self.panes.current];
+        if state.in_archive() {
+            let msg = "Archives are read-only".to_string();
+            state.info.push(Info::new(InfoKind::Message(msg)));
+            return;
+        }
+        let files = App::selection(state);
+        let name = match files.as_slice() {
+            [] => return,
+            [file] => file.file_name(),
+            _ => state.path.file_name(),
+        };
+        let name = format!("{}.tar.gz", name.unwrap_or_default().to_string_lossy());
+        state.mode = Mode::Compress(files, name);
+    }
+
+    fn start_extract(&mut self, here: bool) {
+        let state = &mut self.tabs[self.panes.current];
+        let Some(file) = state.files.get(state.selected) else {
+            return;
+        };
+        let stem = ArchiveKind::stem(&file.path);
+        match stem {
+            Some(stem) if !state.in_archive() && file.is_file().unwrap_or_default() => {
+                if here {
+                    let job = Job::extract(file.path.clone(), state.path.clone());
+                    self.jobs.push(job);
+                } else {
+                    state.mode = Mode::ExtractTo(file.path.clone(), stem);
+                }
+            }
+            _ => {
+                let msg = "Only .tar, .tar.gz, .tar.zst and .zip files can be extracted";
+                state
+                    .info
+                    .push(Info::new(InfoKind::Message(msg.to_string())));
+            }
+        }
+    }
+
+    fn git_action(&mut self, action: GitAction) {
+        let state = &mut self.tabs[self.panes.current];
+        if state.in_archive() || git::root(&state.path).is_none() {
+            let msg = "Not inside a git repository".to_string();
+            state.info.push(Info::new(InfoKind::Message(msg)));
+            return;
+        }
+        let files = App::selection(state);
+        if files.is_empty() {
+            return;
+        }
+        let done = match action {
+            GitAction::Stage => git::stage(&state.path, &files).map(|_| "Staged"),
+            GitAction::Unstage => git::unstage(&state.path, &files).map(|_| "Unstaged"),
+            GitAction::Discard => {
+                state.mode = Mode::DiscardChanges(files, String::new());
+                return;
+            }
+        };
+        App::git_done(state, done, &files);
+    }
+
+    // tells how a git action went and shows its changes
+    fn git_done(state: &mut State, done: Result<&str>, files: &[PathBuf]) {
+        match done {
+            Ok(verb) => {
+                let msg = match files {
+                    [file] => {
+                        let name = file.file_name().unwrap_or_default().to_string_lossy();
+                        format!("{verb} {name}")
+                    }
+                    files => format!("{verb} {} entries", files.len()),
+                };
+                state.marked.clear();
+                state.info.push(Info::new(InfoKind::Message(msg)));
+            }
+            Err(e) => state.info.push(Info::new(InfoKind::Error(e))),
+        }
+        state.git.invalidate();
+    }
+
+    /// Sets up the copy or move prompt, the destination defaults to the other pane
+    fn start_transfer(&mut self, input: &InputResult) {
+        let dest = match self.panes.layout {
+            PaneLayout::Dual => &self.tabs[self.panes.other],
+            PaneLayout::Single | PaneLayout::Miller => self.state(),
+        };
+        if dest.in_archive() || self.state().in_archive() {
+            let msg = "Archives are read-only".to_string();
+            self.tabs[self.panes.current]
+                .info
+                .push(Info::new(InfoKind::Message(msg)));
+            return;
+        }
+        let dest = &dest.path;
+        let dest = dest.to_string_lossy().to_string();
+
+        let state = &mut self.tabs[self.panes.current];
+        let files = App::selection(state);
+        if files.is_empty() {
+            return;
+        }
+        state.mode = match input {
+            InputResult::Move => Mode::MoveFile(files, dest),
+            _ => Mode::CopyFile(files, dest),
+        };
+    }
+
+    async fn handle_input(&mut self, input: InputResult) -> Result<bool> {
+        match input {
+            InputResult::Tab(action) => {
+                self.handle_tab(action);
+                return Ok(false);
+            }
+            InputResult::Pane(action) => {
+                self.handle_pane(action);
+                return Ok(false);
+            }
+            InputResult::Preview(action) => {
+                self.ui.preview.handle(action);
+                return Ok(false);
+            }
+            InputResult::Copy | InputResult::Move => {
+                self.start_transfer(&input);
+                return Ok(false);
+            }
+            InputResult::Compress => {
+                self.start_compress();
+                return Ok(false);
+            }
+            InputResult::Extract { here } => {
+                self.start_extract(here);
+                return Ok(false);
+            }
+            InputResult::Git(action) => {
+                self.git_action(action);
+                return Ok(false);
+            }
+            _ => {}
+        }
+
+        // mouse input is mostly turned into the matching key input
+        let input = match input {
+            InputResult::Click { tab, index, double } => {
+                self.handle_tab(TabAction::Select(tab));
+                let state = &mut self.tabs[self.panes.current];
+                if index >= state.files.len() {
+                    return Ok(false);
+                }
+                state.selected = index;
+                if !double {
+                    return Ok(false);
+                }
+                InputResult::Confirm
+            }
+            InputResult::Scroll { tab, up } => {
+                if let Some(state) = self.tabs.get_mut(tab) {
+                    state.selected = if up {
+                        state.selected.saturating_sub(SCROLL_STEP)
+                    } else {
+                        (state.selected + SCROLL_STEP).min(state.files.len().saturating_sub(1))
+                    };
+                }
+                return Ok(false);
+            }
+            InputResult::GoTo { tab, path } => {
+                self.handle_tab(TabAction::Select(tab));
+                self.tabs[self.panes.current].go_to(path);
+                return Ok(false);
+            }
+            InputResult::Mode(InputModeResult::Select(i)) => {
+                self.tabs[self.panes.current].mode.set_list_selected(i);
+                InputResult::Mode(InputModeResult::Execute)
+            }
+            input => input,
+        };
+
+        let state = &mut self.tabs[self.panes.current];
+        let pick = &mut self.pick;
+        let bookmarks = &mut self.bookmarks;
+        let frecency = &self.frecency;
         match input {
             InputResult::Quit => {
                 return Ok(true);
             }
+            #[allow(clippy::manual_saturating_arithmetic)]
             InputResult::MoveUp => {
                 state.selected = state.selected.checked_sub(1).unwrap_or_default();
             }
@@ -94,16 +599,118 @@ impl App {
                     .unwrap()
                     .clamp(0, state.files.len().saturating_sub(1));
             }
-            InputResult::EnterFolder => {
-                let folder = &state.files[state.selected];
-                if folder.is_dir()? {
-                    state.path = folder.path.clone();
-                    state.selected = 0;
+            InputResult::Confirm if pick.is_some() => {
+                let picker = pick.as_mut().expect("should be there");
+                match picker.confirm(&state.marked, state.files.get(state.selected)) {
+                    Ok(()) => return Ok(true),
+                    Err(e) => state.info.push(Info::new(InfoKind::Error(e))),
                 }
             }
+            InputResult::EnterFolder | InputResult::Confirm => {
+                if let Some(folder) = state.files.get(state.selected) {
+                    // archives are browsed like directories
+                    let is_archive = ArchiveKind::detect(&folder.path).is_some()
+                        && folder.is_file().unwrap_or_default();
+                    if folder.is_dir()? || is_archive {
+                        state.path = folder.path.clone();
+                    }
+                }
+            }
+            InputResult::ToggleMark if pick.as_ref().is_none_or(|p| p.multiple) => {
+                state.toggle_mark();
+                state.selected = state
+                    .selected
+                    .saturating_add(1)
+                    .clamp(0, state.files.len().saturating_sub(1));
+            }
             InputResult::GoBack => {
+                state.pending_select = state.path.file_name().map(|n| n.to_os_string());
                 state.path.pop();
-                state.selected = 0;
+            }
+            InputResult::Back => match state.history.back() {
+                Some(path) => state.path = path,
+                None => state.info.push(Info::new(InfoKind::Message(
+                    "Already at the oldest directory".to_string(),
+                ))),
+            },
+            InputResult::Forward => match state.history.forward() {
+                Some(path) => state.path = path,
+                None => state.info.push(Info::new(InfoKind::Message(
+                    "Already at the newest directory".to_string(),
+                ))),
+            },
+            InputResult::ToggleHidden => {
+                state.list.show_hidden = !state.list.show_hidden;
+                let msg = if state.list.show_hidden {
+                    "Showing hidden files"
+                } else {
+                    "Hiding hidden files"
+                };
+                state
+                    .info
+                    .push(Info::new(InfoKind::Message(msg.to_string())));
+            }
+            InputResult::CycleSort => {
+                state.list.sort = state.list.sort.next();
+                state.info.push(Info::new(InfoKind::Message(format!(
+                    "Sorting by {}",
+                    state.list.sort.name()
+                ))));
+            }
+            InputResult::ReverseSort => {
+                state.list.reverse = !state.list.reverse;
+            }
+            InputResult::Bookmark(action) => {
+                let Mode::Bookmarks(i) = state.mode else {
+                    return Ok(false);
+                };
+                let Some((name, _)) = bookmarks.get_index(i) else {
+                    return Ok(false);
+                };
+                let name = name.clone();
+                match action {
+                    BookmarkAction::Delete => {
+                        if let Err(e) = bookmarks.remove(&name) {
+                            state.info.push(Info::new(InfoKind::Error(e)));
+                        }
+                        state.mode = Mode::Bookmarks(i.min(bookmarks.len().saturating_sub(1)));
+                    }
+                    BookmarkAction::Rename => {
+                        state.mode = Mode::RenameBookmark(name.clone(), name);
+                    }
+                }
+            }
+            InputResult::Mode(InputModeResult::AddChar(c))
+                if matches!(state.mode, Mode::SetMark | Mode::GotoMark) =>
+            {
+                let name = c.to_string();
+                match core::mem::take(&mut state.mode) {
+                    Mode::SetMark => match bookmarks.set(&name, &state.path) {
+                        Ok(()) => state
+                            .info
+                            .push(Info::new(InfoKind::Message(format!("Set mark '{c}'")))),
+                        Err(e) => state.info.push(Info::new(InfoKind::Error(e))),
+                    },
+                    _ => match bookmarks.get(&name) {
+                        // stale marks are handled like any other unreadable path
+                        Some(path) => state.path = path.clone(),
+                        None => state
+                            .info
+                            .push(Info::new(InfoKind::Error(eyre!("No mark '{c}'")))),
+                    },
+                }
+            }
+            InputResult::Mode(InputModeResult::Complete) => {
+                if let Mode::GoTo(input) = &mut state.mode {
+                    *input = filesystem::path::complete(input, &state.path);
+                }
+            }
+            InputResult::Mode(InputModeResult::ModeChange(
+                Mode::CreateFile(_) | Mode::RenameFile(..) | Mode::DeleteFile(..),
+            )) if state.in_archive() => {
+                state.info.push(Info::new(InfoKind::Message(
+                    "Archives are read-only".to_string(),
+                )));
             }
             InputResult::Mode(InputModeResult::ModeChange(m)) => {
                 state.mode = m;
@@ -114,19 +721,133 @@ impl App {
             InputResult::Mode(InputModeResult::RemoveChar) => {
                 state.mode.remove_char();
             }
+            InputResult::Mode(m @ (InputModeResult::MoveUp | InputModeResult::MoveDown)) => {
+                let len = match state.mode {
+                    Mode::Ancestor(_) => state.path.ancestors().count(),
+                    Mode::History(_) => state.history.recent.len(),
+                    Mode::Bookmarks(_) => bookmarks.len(),
+                    Mode::Jump(ref query, _) => frecency.query(query, JUMP_MATCHES).len(),
+                    _ => 0,
+                };
+                state
+                    .mode
+                    .move_list_selected(matches!(m, InputModeResult::MoveUp), len);
+            }
             InputResult::Mode(InputModeResult::Execute) => {
                 let mut mode = Mode::Basic;
                 core::mem::swap(&mut state.mode, &mut mode);
                 match mode {
                     Mode::CreateFile(file) => {
-                        if let Err(e) = filesystem::modify::create_file(&file, &state.path).await {
-                            state.info.push(Info::new(InfoKind::Error(e)));
+                        match filesystem::modify::create_file(&file, &state.path).await {
+                            Ok(()) => {
+                                // select what was just created
+                                state.pending_select =
+                                    filesystem::path::entry_in(&state.path, &file);
+                            }
+                            Err(e) => state.info.push(Info::new(InfoKind::Error(e))),
                         }
                     }
                     Mode::RenameFile(from, new) => {
-                        if let Err(e) = filesystem::modify::rename_file(&from, &new).await {
+                        match filesystem::modify::rename_file(&from, &new).await {
+                            Ok(()) => {
+                                state.pending_select =
+                                    PathBuf::from(&new).file_name().map(|n| n.to_os_string());
+                            }
+                            Err(e) => state.info.push(Info::new(InfoKind::Error(e))),
+                        }
+                    }
+                    Mode::CopyFile(files, dest) => {
+                        let dest = state.path.join(dest);
+                        match filesystem::modify::copy_files(&files, &dest).await {
+                            Ok(()) => state.marked.clear(),
+                            Err(e) => state.info.push(Info::new(InfoKind::Error(e))),
+                        }
+                    }
+                    Mode::MoveFile(files, dest) => {
+                        let dest = state.path.join(dest);
+                        match filesystem::modify::move_files(&files, &dest).await {
+                            Ok(()) => state.marked.clear(),
+                            Err(e) => state.info.push(Info::new(InfoKind::Error(e))),
+                        }
+                    }
+                    Mode::Compress(files, name) => {
+                        let dest = state.path.join(name);
+                        state.marked.clear();
+                        self.jobs.push(Job::compress(files, dest));
+                    }
+                    Mode::ExtractTo(file, name) => {
+                        let dest = state.path.join(name);
+                        self.jobs.push(Job::extract(file, dest));
+                    }
+                    Mode::History(i) => {
+                        if let Some((path, _)) = state.history.recent.get(i) {
+                            state.path = path.clone();
+                        }
+                    }
+                    Mode::Bookmarks(i) => {
+                        if let Some((_, path)) = bookmarks.get_index(i) {
+                            state.path = path.clone();
+                        }
+                    }
+                    Mode::GoTo(input) => {
+                        let path = filesystem::path::expand(&input, &state.path);
+                        let path = path.canonicalize().unwrap_or(path);
+                        match path.parent() {
+                            // select the file in its directory
+                            Some(parent) if path.is_file() => {
+                                state.pending_select = path.file_name().map(|n| n.to_os_string());
+                                state.path = parent.to_path_buf();
+                            }
+                            _ => state.path = path,
+                        }
+                    }
+                    Mode::HexOffset(_) if !self.ui.preview.is_hex() => {
+                        state.info.push(Info::new(InfoKind::Message(
+                            "Only hex previews can go to an offset".to_string(),
+                        )));
+                    }
+                    Mode::HexOffset(input) => match parse_offset(&input) {
+                        Ok(offset) => self.ui.preview.jump_to(offset),
+                        Err(e) => state.info.push(Info::new(InfoKind::Error(e))),
+                    },
+                    Mode::Encoding(label) if label.trim().is_empty() => {
+                        self.ui.preview.encoding = None;
+                    }
+                    Mode::Encoding(label) => match Encoding::for_label(label.trim().as_bytes()) {
+                        Some(encoding) => self.ui.preview.encoding = Some(encoding),
+                        None => state.info.push(Info::new(InfoKind::Error(eyre!(
+                            "Unknown encoding: \"{}\"",
+                            label.trim()
+                        )))),
+                    },
+                    Mode::Ancestor(i) => {
+                        if let Some(path) = state.path.ancestors().nth(i) {
+                            state.go_to(path.to_path_buf());
+                        }
+                    }
+                    Mode::Jump(query, i) => {
+                        if let Some(path) = frecency.query(&query, JUMP_MATCHES).get(i) {
+                            state.path = path.to_path_buf();
+                        }
+                    }
+                    Mode::AddBookmark(name) => match bookmarks.set(&name, &state.path) {
+                        Ok(()) => state.info.push(Info::new(InfoKind::Message(format!(
+                            "Bookmarked as \"{}\"",
+                            name.trim()
+                        )))),
+                        Err(e) => state.info.push(Info::new(InfoKind::Error(e))),
+                    },
+                    Mode::RenameBookmark(from, to) => {
+                        if let Err(e) = bookmarks.rename(&from, &to) {
                             state.info.push(Info::new(InfoKind::Error(e)));
                         }
+                        // back to the bookmark manager
+                        state.mode = Mode::Bookmarks(0);
+                    }
+                    Mode::DiscardChanges(files, confirm) if confirm.to_lowercase() == "y" => {
+                        let done =
+                            git::discard(&state.path, &files).map(|_| "Discarded the changes to");
+                        App::git_done(state, done, &files);
                     }
                     Mode::DeleteFile(file, confirm) if confirm.to_lowercase() == "y" => {
                         if let Err(e) = filesystem::modify::delete_file(&file).await {
@@ -145,5 +866,117 @@ impl App {
 #[tokio::main]
 async fn main() -> Result<()> {
     let settings = parse_args();
-    App::new(PathBuf::from(&settings.dir))?.run().await
+    if let Some(shell) = settings.init {
+        print!("{}", shell::init(shell));
+        return Ok(());
+    }
+
+    let dirs = match &settings.jump {
+        Some(query) => {
+            let frecency = Frecency::load()?;
+            let path = frecency
+                .query(query, 1)
+                .first()
+                .ok_or_else(|| eyre!("No directory matches \"{query}\""))?
+                .to_path_buf();
+            vec![path]
+        }
+        None => settings.dirs.clone(),
+    };
+
+    if settings.list {
+        let [dir] = dirs.as_slice() else {
+            return Err(eyre!("--list takes a single directory"));
+        };
+        return list::list(dir, &settings.list_options(), settings.format).await;
+    }
+
+    let mut app = App::new(
+        &dirs,
+        settings.list_options(),
+        settings.preview_options(),
+        settings.previewers(),
+        settings.image_protocol,
+    )?;
+    if settings.pick {
+        app.pick = Some(Picker::new(&settings));
+    }
+    let result = app.run(settings.keeps_stdout()).await;
+    // visits are only written out once, on exit
+    app.frecency.save()?;
+    result?;
+
+    if let Some(file) = &settings.choose_dir {
+        shell::write_last_dir(file, app.state().dir_on_disk())?;
+    }
+    if settings.print_last_dir {
+        println!("{}", shell::display_path(app.state().dir_on_disk()));
+    }
+    if let Some(picker) = &app.pick {
+        match &picker.chosen {
+            Some(paths) => pick::write_chosen(paths, settings.output.as_deref(), settings.null)?,
+            // cancelled
+            None => std::process::exit(1),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::filesystem::sort::SortBy;
+
+    #[tokio::test]
+    async fn list_per_tab_tests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().canonicalize().unwrap();
+        fs::write(dir.join("a.txt"), "1234").unwrap();
+        fs::write(dir.join("b.rs"), "12").unwrap();
+        fs::write(dir.join(".hidden"), "").unwrap();
+
+        let tab = || State {
+            path: dir.clone(),
+            ..Default::default()
+        };
+        let mut app = App {
+            ui: ui::UiState::default(),
+            tabs: vec![tab(), tab()],
+            panes: Panes::default(),
+            pick: None,
+            bookmarks: Bookmarks::default(),
+            frecency: Frecency::default(),
+            jobs: Vec::new(),
+        };
+        app.handle_input(InputResult::ToggleHidden).await.unwrap();
+        app.handle_input(InputResult::Tab(TabAction::Next))
+            .await
+            .unwrap();
+        app.handle_input(InputResult::CycleSort).await.unwrap();
+        app.handle_input(InputResult::ReverseSort).await.unwrap();
+
+        let mut names = Vec::new();
+        for state in app.tabs.iter_mut() {
+            App::refresh(state, false, &mut Frecency::default())
+                .await
+                .unwrap();
+            names.push(
+                state
+                    .files
+                    .iter()
+                    .map(|f| f.name.to_string_lossy().to_string())
+                    .collect::<Vec<_>>(),
+            );
+        }
+        assert_eq!(app.tabs[0].list.sort, SortBy::Name);
+        assert!(app.tabs[0].list.show_hidden);
+        assert_eq!(app.tabs[1].list.sort, SortBy::Size);
+        assert!(!app.tabs[1].list.show_hidden);
+        assert_eq!(
+            names,
+            [vec![".hidden", "a.txt", "b.rs"], vec!["a.txt", "b.rs"]]
+        );
+    }
 }
@@ -0,0 +1,128 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use eyre::{eyre, Context, Result};
+
+use crate::{filesystem::read::File, settings::Settings};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum PickFilter {
+    Any,
+    Dirs,
+    Files,
+}
+
+pub struct Picker {
+    pub multiple: bool,
+    pub filter: PickFilter,
+    // set when the selection is confirmed, stays `None` on cancel
+    pub chosen: Option<Vec<PathBuf>>,
+}
+
+impl Picker {
+    pub fn new(settings: &Settings) -> Self {
+        let filter = if settings.dirs_only {
+            PickFilter::Dirs
+        } else if settings.files_only {
+            PickFilter::Files
+        } else {
+            PickFilter::Any
+        };
+        Self {
+            multiple: settings.multiple,
+            filter,
+            chosen: None,
+        }
+    }
+
+    pub fn accepts(&self, file: &File) -> bool {
+        match self.filter {
+            PickFilter::Any => true,
+            PickFilter::Dirs => file.is_dir().unwrap_or_default(),
+            PickFilter::Files => file.is_file().unwrap_or_default(),
+        }
+    }
+
+    fn accepts_path(&self, path: &Path) -> bool {
+        match self.filter {
+            PickFilter::Any => path.exists(),
+            PickFilter::Dirs => path.is_dir(),
+            PickFilter::Files => path.is_file(),
+        }
+    }
+
+    /// Works out what should be chosen: the marked paths when picking multiple, otherwise the
+    /// selected file
+    pub fn confirm(&mut self, marked: &HashSet<PathBuf>, selected: Option<&File>) -> Result<()> {
+        let mut paths = if self.multiple && !marked.is_empty() {
+            let mut paths = marked
+                .iter()
+                .filter(|p| self.accepts_path(p))
+                .cloned()
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths
+        } else {
+            selected
+                .filter(|f| self.accepts(f))
+                .map(|f| vec![f.path.clone()])
+                .unwrap_or_default()
+        };
+
+        if paths.is_empty() {
+            return Err(eyre!(match self.filter {
+                PickFilter::Any => "Nothing to pick",
+                PickFilter::Dirs => "Only directories can be picked",
+                PickFilter::Files => "Only files can be picked",
+            }));
+        }
+        for path in paths.iter_mut() {
+            *path = path
+                .canonicalize()
+                .wrap_err_with(|| format!("Could not resolve: \"{}\"", path.display()))?;
+        }
+        self.chosen = Some(paths);
+        Ok(())
+    }
+}
+
+/// The picked paths as they are written out, each ended by a newline or NUL
+fn format_chosen(paths: &[PathBuf], null: bool) -> String {
+    let sep = if null { '\0' } else { '\n' };
+    let mut out = String::new();
+    for path in paths {
+        out.push_str(&crate::shell::display_path(path));
+        out.push(sep);
+    }
+    out
+}
+
+pub fn write_chosen(paths: &[PathBuf], output: Option<&Path>, null: bool) -> Result<()> {
+    let out = format_chosen(paths, null);
+    match output {
+        Some(file) => fs::write(file, out)
+            .wrap_err_with(|| format!("Could not write picked paths to: \"{}\"", file.display())),
+        None => {
+            let mut stdout = io::stdout();
+            stdout.write_all(out.as_bytes())?;
+            Ok(stdout.flush()?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn format_chosen_tests() {
+        let paths = [PathBuf::from("/a/b c"), PathBuf::from("/d")];
+        assert_eq!(format_chosen(&paths, false), "/a/b c\n/d\n");
+        assert_eq!(format_chosen(&paths, true), "/a/b c\0/d\0");
+        assert_eq!(format_chosen(&[], false), "");
+    }
+}
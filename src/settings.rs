@@ -1,7 +1,18 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 use clap::Parser;
 
+use crate::{
+    filesystem::{
+        preview::{PreviewOptions, DEFAULT_MAX_BYTES, DEFAULT_MAX_LINES},
+        previewer::{Previewer, Previewers, DEFAULT_TIMEOUT},
+        sort::{ListOptions, SortBy},
+    },
+    list::ListFormat,
+    shell::Shell,
+    ui::graphics::ImageProtocol,
+};
+
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 static HELP_TEMPLATE: &str = "\
 {before-help}{name} {version}
@@ -16,9 +27,133 @@ static HELP_TEMPLATE: &str = "\
 #[derive(Parser)]
 #[command(name = "onyx", bin_name = "ox", author = "YummyOreo", version = VERSION, about = "A command line terminal", help_template(HELP_TEMPLATE),)]
 pub struct Settings {
-    // the dir that should be opened
-    #[arg(default_value = "./")]
-    pub dir: PathBuf,
+    // the dirs that should be opened, one tab each
+    #[arg(value_name = "DIR", default_value = "./")]
+    pub dirs: Vec<PathBuf>,
+
+    /// Write the last directory to this file on exit
+    #[arg(long, value_name = "FILE")]
+    pub choose_dir: Option<PathBuf>,
+
+    /// Print the last directory to stdout on exit
+    #[arg(long)]
+    pub print_last_dir: bool,
+
+    /// Print the shell function that cds into the last directory on exit
+    #[arg(long, value_name = "SHELL")]
+    pub init: Option<Shell>,
+
+    /// Use onyx as a chooser, Enter prints the picked paths and exits
+    #[arg(long)]
+    pub pick: bool,
+
+    /// Allow picking multiple marked entries
+    #[arg(long, requires = "pick")]
+    pub multiple: bool,
+
+    /// Only allow picking directories
+    #[arg(long, requires = "pick", conflicts_with = "files_only")]
+    pub dirs_only: bool,
+
+    /// Only allow picking files
+    #[arg(long, requires = "pick")]
+    pub files_only: bool,
+
+    /// Write the picked paths to this file instead of stdout
+    #[arg(long, value_name = "FILE", requires = "pick")]
+    pub output: Option<PathBuf>,
+
+    /// Separate the picked paths with NUL instead of newlines
+    #[arg(long, requires = "pick")]
+    pub null: bool,
+
+    /// Print the directory listing without starting the ui
+    #[arg(long)]
+    pub list: bool,
+
+    /// The format used by `--list`
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "plain",
+        requires = "list"
+    )]
+    pub format: ListFormat,
+
+    /// What to sort the listing by
+    #[arg(long, value_name = "KEY", default_value = "name")]
+    pub sort: SortBy,
+
+    /// Reverse the sort order
+    #[arg(long)]
+    pub reverse: bool,
+
+    /// Open the most frecent directory that matches the query
+    #[arg(long, value_name = "QUERY", conflicts_with = "dirs")]
+    pub jump: Option<String>,
+
+    /// Show hidden files
+    #[arg(short, long)]
+    pub all: bool,
+
+    /// The most bytes of a file read for its preview
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_MAX_BYTES)]
+    pub preview_bytes: u64,
+
+    /// The most lines of a file shown in its preview
+    #[arg(long, value_name = "LINES", default_value_t = DEFAULT_MAX_LINES)]
+    pub preview_lines: usize,
+
+    /// How images are previewed, auto picks by the terminal
+    #[arg(long, value_name = "PROTOCOL", default_value = "auto")]
+    pub image_protocol: ImageProtocol,
+
+    /// Preview files with a command, e.g. `pdf=pdftotext "$1" -` or `video/*=mediainfo "$1"`.
+    /// Can be given multiple times, the first match is used
+    #[arg(long = "previewer", value_name = "MATCH=COMMAND")]
+    pub previewers: Vec<Previewer>,
+
+    /// How many seconds a previewer may take before it is stopped
+    #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_TIMEOUT)]
+    pub previewer_timeout: u64,
+}
+
+impl Settings {
+    pub fn list_options(&self) -> ListOptions {
+        ListOptions {
+            sort: self.sort,
+            reverse: self.reverse,
+            show_hidden: self.all,
+        }
+    }
+
+    /// Whether stdout is only for what is printed on exit, e.g. the picked paths
+    pub fn keeps_stdout(&self) -> bool {
+        self.pick || self.print_last_dir
+    }
+
+    pub fn previewers(&self) -> Previewers {
+        Previewers::new(
+            self.previewers.clone(),
+            Duration::from_secs(self.previewer_timeout),
+        )
+    }
+
+    pub fn preview_options(&self) -> PreviewOptions {
+        PreviewOptions {
+            max_bytes: self.preview_bytes,
+            max_lines: self.preview_lines,
+        }
+    }
+}
+
+/// Where onyx keeps its data files, can be changed with `ONYX_DATA_DIR`
+pub fn data_path(file: &str) -> Option<PathBuf> {
+    let dir = match std::env::var_os("ONYX_DATA_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::data_dir()?.join("onyx"),
+    };
+    Some(dir.join(file))
 }
 
 pub fn parse_args() -> Settings {
@@ -0,0 +1,58 @@
+use std::{fs, path::Path};
+
+use clap::ValueEnum;
+use eyre::{Context, Result};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+// bash and zsh share the same posix function
+const POSIX_INIT: &str = r#"ox() {
+    local tmp
+    tmp="$(mktemp -t onyx-cwd.XXXXXX)" || return
+    command ox --choose-dir "$tmp" "$@"
+    local code=$?
+    local dir
+    dir="$(cat -- "$tmp")"
+    rm -f -- "$tmp"
+    if [ -n "$dir" ] && [ "$dir" != "$PWD" ]; then
+        builtin cd -- "$dir" || return
+    fi
+    return $code
+}
+"#;
+
+const FISH_INIT: &str = r#"function ox
+    set -l tmp (mktemp -t onyx-cwd.XXXXXX); or return
+    command ox --choose-dir $tmp $argv
+    set -l code $status
+    set -l dir (cat -- $tmp)
+    rm -f -- $tmp
+    if test -n "$dir"; and test "$dir" != "$PWD"
+        builtin cd -- $dir; or return
+    end
+    return $code
+end
+"#;
+
+/// The shell function that wraps `ox` and cds into the last directory on exit
+pub fn init(shell: Shell) -> &'static str {
+    match shell {
+        Shell::Bash | Shell::Zsh => POSIX_INIT,
+        Shell::Fish => FISH_INIT,
+    }
+}
+
+/// Removes the windows `\\?\` prefix so the path can be used by the shell
+pub fn display_path(path: &Path) -> String {
+    path.to_string_lossy().replace("\\\\?\\", "")
+}
+
+pub fn write_last_dir(file: &Path, path: &Path) -> Result<()> {
+    fs::write(file, display_path(path))
+        .wrap_err_with(|| format!("Could not write last dir to: \"{}\"", file.display()))
+}
@@ -1,11 +1,21 @@
 use std::{
-    path::PathBuf,
-    time::{Duration, Instant},
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
 };
 
 use eyre::Report;
 
-use crate::filesystem::read::File;
+use crate::{
+    filesystem::{
+        archive::{self, Archive, Opening},
+        read::File,
+        sort::ListOptions,
+    },
+    git::StatusTracker,
+    history::History,
+};
 
 #[derive(PartialEq, Eq)]
 pub enum Mode {
@@ -13,8 +23,54 @@ pub enum Mode {
     CreateFile(String),
     RenameFile(PathBuf, String),
     DeleteFile(PathBuf, String),
+    CopyFile(Vec<PathBuf>, String),
+    MoveFile(Vec<PathBuf>, String),
+    // the selected entry of the history popup
+    History(usize),
+    // waiting for the key of the mark
+    SetMark,
+    GotoMark,
+    AddBookmark(String),
+    // the selected entry of the bookmark manager
+    Bookmarks(usize),
+    RenameBookmark(String, String),
+    // the query and the selected match
+    Jump(String, usize),
+    GoTo(String),
+    // how many directories up the chosen ancestor is
+    Ancestor(usize),
+    HexOffset(String),
+    // the label of the encoding the preview is forced to
+    Encoding(String),
+    // the files and the name of the archive
+    Compress(Vec<PathBuf>, String),
+    // the archive and the folder it goes to
+    ExtractTo(PathBuf, String),
+    // the files whose unstaged changes are thrown away, once confirmed
+    DiscardChanges(Vec<PathBuf>, String),
+}
+
+// the modes with a text prompt, `$s` binds what was typed
+macro_rules! input_modes {
+    ($s:ident) => {
+        Mode::CreateFile($s)
+            | Mode::RenameFile(_, $s)
+            | Mode::DeleteFile(_, $s)
+            | Mode::CopyFile(_, $s)
+            | Mode::MoveFile(_, $s)
+            | Mode::AddBookmark($s)
+            | Mode::RenameBookmark(_, $s)
+            | Mode::Jump($s, _)
+            | Mode::GoTo($s)
+            | Mode::HexOffset($s)
+            | Mode::Encoding($s)
+            | Mode::Compress(_, $s)
+            | Mode::ExtractTo(_, $s)
+            | Mode::DiscardChanges(_, $s)
+    };
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Mode {
     fn default() -> Self {
         Self::Basic
@@ -22,26 +78,84 @@ impl Default for Mode {
 }
 
 impl Mode {
-    pub fn add_char(&mut self, c: char) {
+    fn input_mut(&mut self) -> Option<&mut String> {
         match self {
-            Self::CreateFile(s) | Self::RenameFile(_, s) | Mode::DeleteFile(_, s) => s.push(c),
-            _ => {}
+            input_modes!(s) => Some(s),
+            _ => None,
+        }
+    }
+    pub fn add_char(&mut self, c: char) {
+        if let Some(s) = self.input_mut() {
+            s.push(c);
         }
+        self.reset_list_selected();
     }
     pub fn remove_char(&mut self) {
-        match self {
-            Self::CreateFile(s) | Self::RenameFile(_, s) | Mode::DeleteFile(_, s) => {
-                s.pop();
-            }
-            _ => {}
+        if let Some(s) = self.input_mut() {
+            s.pop();
+        }
+        self.reset_list_selected();
+    }
+    // the matches change with the input
+    fn reset_list_selected(&mut self) {
+        if let Self::Jump(_, i) = self {
+            *i = 0;
         }
     }
     pub fn get_str(&self) -> Option<&str> {
         match self {
-            Self::CreateFile(s) | Self::RenameFile(_, s) | Mode::DeleteFile(_, s) => Some(s),
+            input_modes!(s) => Some(s),
             _ => None,
         }
     }
+
+    /// The selected entry of modes that show a list instead of a prompt
+    pub fn get_list_selected(&self) -> Option<usize> {
+        match self {
+            Self::History(i) | Self::Bookmarks(i) | Self::Jump(_, i) => Some(*i),
+            _ => None,
+        }
+    }
+    pub fn set_list_selected(&mut self, index: usize) {
+        if let Self::History(i) | Self::Bookmarks(i) | Self::Jump(_, i) = self {
+            *i = index;
+        }
+    }
+    pub fn move_list_selected(&mut self, up: bool, len: usize) {
+        if let Self::History(i) | Self::Bookmarks(i) | Self::Jump(_, i) | Self::Ancestor(i) = self {
+            *i = if up {
+                i.saturating_sub(1)
+            } else {
+                (*i + 1).min(len.saturating_sub(1))
+            };
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum PaneLayout {
+    #[default]
+    Single,
+    Dual,
+    Miller,
+}
+
+impl PaneLayout {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Single => Self::Dual,
+            Self::Dual => Self::Miller,
+            Self::Miller => Self::Single,
+        }
+    }
+}
+
+/// Which tabs are on screen, in the dual layout `other` is the tab shown in the inactive pane
+#[derive(Default)]
+pub struct Panes {
+    pub layout: PaneLayout,
+    pub current: usize,
+    pub other: usize,
 }
 
 pub enum InfoKind {
@@ -68,12 +182,73 @@ pub struct State {
     pub path: PathBuf,
     pub last_path: PathBuf,
     pub files: Vec<File>,
+    // only read for the miller columns layout
+    pub parent_files: Vec<File>,
+    // the parent directory, when it was modified and the options it was listed with
+    pub parent_read: Option<(PathBuf, SystemTime, ListOptions)>,
+    pub list: ListOptions,
     pub selected: usize,
+    // selected by name once the files are read, e.g. the folder we just left
+    pub pending_select: Option<OsString>,
+    // the last selected entry of each visited directory
+    pub remembered: HashMap<PathBuf, OsString>,
+    pub history: History,
+    // shown next to the path
+    pub branch: Option<String>,
+    // the git status of the files, when in a repository
+    pub git: StatusTracker,
+    pub free_space: Option<u64>,
+    // the archive being browsed, its listing is kept until it changes
+    pub archive: Option<Archive>,
+    // the archive read in the background, the listing shows it is loading until then
+    pub opening: Option<Opening>,
+    pub marked: HashSet<PathBuf>,
     pub mode: Mode,
     pub info: Vec<Info>,
 }
 
 impl State {
+    pub fn toggle_mark(&mut self) {
+        if let Some(file) = self.files.get(self.selected) {
+            if !self.marked.remove(&file.path) {
+                self.marked.insert(file.path.clone());
+            }
+        }
+    }
+
+    /// If the current path is inside an archive, nothing can be changed there
+    pub fn in_archive(&self) -> bool {
+        self.archive
+            .as_ref()
+            .is_some_and(|a| self.path.starts_with(&a.path))
+    }
+
+    /// If the archive the current path is in is still being read
+    pub fn opening_archive(&self) -> bool {
+        self.opening
+            .as_ref()
+            .is_some_and(|o| self.path.starts_with(&o.path))
+    }
+
+    /// The directory on disk the shell can go to, the one holding the archive when inside one
+    pub fn dir_on_disk(&self) -> &Path {
+        match archive::split(&self.path) {
+            Some((archive, _)) => archive.parent().unwrap_or(archive),
+            None => &self.path,
+        }
+    }
+
+    /// Goes to `path`, selecting the directory that was left when going up
+    pub fn go_to(&mut self, path: PathBuf) {
+        if let Ok(rest) = self.path.strip_prefix(&path) {
+            self.pending_select = rest
+                .components()
+                .next()
+                .map(|c| c.as_os_str().to_os_string());
+        }
+        self.path = path;
+    }
+
     pub async fn purge_info(infos: &mut Vec<Info>, d: Duration) {
         infos.retain(|i| i.time.elapsed() < d);
     }
@@ -0,0 +1,193 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+// tabs are expanded, the preview does not know how wide they are
+const TAB_WIDTH: usize = 4;
+
+/// The basic and bright colors by their index
+fn indexed(n: u8) -> Color {
+    match n {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        7 => Color::Gray,
+        8 => Color::DarkGray,
+        9 => Color::LightRed,
+        10 => Color::LightGreen,
+        11 => Color::LightYellow,
+        12 => Color::LightBlue,
+        13 => Color::LightMagenta,
+        14 => Color::LightCyan,
+        15 => Color::White,
+        n => Color::Indexed(n),
+    }
+}
+
+// the color after `38;` or `48;`, either `5;n` or `2;r;g;b`
+fn extended(params: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    let mut next = || params.next().map(|p| p.min(255) as u8);
+    match next()? {
+        5 => Some(indexed(next()?)),
+        2 => Some(Color::Rgb(next()?, next()?, next()?)),
+        _ => None,
+    }
+}
+
+/// Applies the parameters of a `CSI ... m` sequence to `style`
+fn apply_sgr(style: Style, params: &str) -> Style {
+    let mut params = params
+        .split([';', ':'])
+        .map(|p| p.parse::<u16>().unwrap_or(0));
+    let mut style = style;
+    // an empty sequence resets too
+    let Some(mut param) = params.next() else {
+        return Style::default();
+    };
+    loop {
+        style = match param {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 | 6 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            8 => style.add_modifier(Modifier::HIDDEN),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            21 | 22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            28 => style.remove_modifier(Modifier::HIDDEN),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            n @ 30..=37 => style.fg(indexed((n - 30) as u8)),
+            38 => extended(&mut params).map_or(style, |c| style.fg(c)),
+            39 => Style { fg: None, ..style },
+            n @ 40..=47 => style.bg(indexed((n - 40) as u8)),
+            48 => extended(&mut params).map_or(style, |c| style.bg(c)),
+            49 => Style { bg: None, ..style },
+            n @ 90..=97 => style.fg(indexed((n - 90 + 8) as u8)),
+            n @ 100..=107 => style.bg(indexed((n - 100 + 8) as u8)),
+            _ => style,
+        };
+        match params.next() {
+            Some(next) => param = next,
+            None => return style,
+        }
+    }
+}
+
+/// Turns text with ANSI escape codes into styled lines. Colors and text attributes are kept,
+/// any other escape code is left out
+pub fn to_lines(text: &str) -> Vec<Line<'static>> {
+    let mut lines = vec![];
+    let mut spans = vec![];
+    let mut content = String::new();
+    let mut style = Style::default();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => {
+                match chars.next() {
+                    // CSI, parameters up to a final byte
+                    Some('[') => {
+                        let mut params = String::new();
+                        for c in chars.by_ref() {
+                            if ('\x40'..='\x7e').contains(&c) {
+                                if c == 'm' {
+                                    if !content.is_empty() {
+                                        spans.push(Span::styled(
+                                            std::mem::take(&mut content),
+                                            style,
+                                        ));
+                                    }
+                                    style = apply_sgr(style, &params);
+                                }
+                                break;
+                            }
+                            params.push(c);
+                        }
+                    }
+                    // OSC, like titles or links, up to BEL or ST
+                    Some(']') => {
+                        while let Some(c) = chars.next() {
+                            if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                                break;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            '\n' => {
+                if !content.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut content), style));
+                }
+                lines.push(Line::from(std::mem::take(&mut spans)));
+            }
+            '\t' => {
+                let width =
+                    spans.iter().map(|s: &Span| s.width()).sum::<usize>() + content.chars().count();
+                content.push_str(&" ".repeat(TAB_WIDTH - width % TAB_WIDTH));
+            }
+            c if c.is_control() => {}
+            c => content.push(c),
+        }
+    }
+    if !content.is_empty() {
+        spans.push(Span::styled(content, style));
+    }
+    if !spans.is_empty() {
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn to_lines_tests() {
+        let lines =
+            to_lines("\x1b[1;31mred\x1b[22m thin\x1b[0m plain\n\x1b]0;title\x07a\tb\x1b[K\n");
+        assert_eq!(lines.len(), 2);
+        let [red, thin, plain] = &lines[0].spans[..] else {
+            panic!("expected three spans");
+        };
+        assert_eq!(red.content, "red");
+        assert_eq!(red.style.fg, Some(Color::Red));
+        assert!(red.style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(thin.style.fg, Some(Color::Red));
+        assert!(!thin.style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(plain, &Span::raw(" plain"));
+        assert_eq!(lines[1].spans, [Span::raw("a   b")]);
+
+        let lines = to_lines("\x1b[38;5;208;48;2;1;2;3mx\x1b[39;94my\x1b[m");
+        assert_eq!(
+            lines[0].spans,
+            [
+                Span::styled(
+                    "x",
+                    Style::default()
+                        .fg(Color::Indexed(208))
+                        .bg(Color::Rgb(1, 2, 3))
+                ),
+                Span::styled(
+                    "y",
+                    Style::default()
+                        .fg(Color::LightBlue)
+                        .bg(Color::Rgb(1, 2, 3))
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::path::{Component, Path, PathBuf, MAIN_SEPARATOR};
+
+use ratatui::text::Span;
+
+use crate::shell::display_path;
+
+const ELLIPSIS: &str = "…";
+
+/// One part of the path bar, `path` is where clicking it goes
+#[derive(Debug, PartialEq)]
+pub struct Crumb {
+    pub text: String,
+    pub path: Option<PathBuf>,
+}
+
+impl Crumb {
+    /// Parts ending in a separator, like the root, are not followed by another one
+    pub fn needs_separator(&self) -> bool {
+        !self.text.ends_with(['/', '\\'])
+    }
+}
+
+/// Splits `path` into its parts, everything up to `home` becomes `~`
+pub fn crumbs(path: &Path, home: Option<&Path>) -> Vec<Crumb> {
+    let mut crumbs = vec![];
+    let mut current = PathBuf::new();
+    let mut rest = path;
+    if let Some(home) = home.filter(|h| h.parent().is_some()) {
+        if let Ok(stripped) = path.strip_prefix(home) {
+            crumbs.push(Crumb {
+                text: "~".to_string(),
+                path: Some(home.to_path_buf()),
+            });
+            current = home.to_path_buf();
+            rest = stripped;
+        }
+    }
+
+    for component in rest.components() {
+        current.push(component);
+        let text = match component {
+            Component::RootDir => MAIN_SEPARATOR.to_string(),
+            // Remove some windows stuff
+            Component::Prefix(p) => display_path(Path::new(p.as_os_str())),
+            c => c.as_os_str().to_string_lossy().to_string(),
+        };
+        crumbs.push(Crumb {
+            text,
+            path: Some(current.clone()),
+        });
+    }
+    crumbs
+}
+
+pub fn width(crumbs: &[Crumb]) -> usize {
+    let separators = crumbs.windows(2).filter(|w| w[0].needs_separator()).count();
+    crumbs
+        .iter()
+        .map(|c| Span::raw(c.text.as_str()).width())
+        .sum::<usize>()
+        + separators
+}
+
+/// Replaces parts in the middle with `…` until the path fits in `max`. The first and last part
+/// and the part leading to `keep` always stay
+pub fn shorten(mut crumbs: Vec<Crumb>, max: usize, keep: Option<&Path>) -> Vec<Crumb> {
+    while width(&crumbs) > max {
+        let end = crumbs.len().saturating_sub(1);
+        let Some(i) = (1..end).find(|&i| {
+            crumbs[i].path.is_some() && keep.is_none_or(|k| crumbs[i].path.as_deref() != Some(k))
+        }) else {
+            break;
+        };
+        // next to an ellipsis the part can just be dropped
+        if crumbs[i - 1].path.is_none() || crumbs[i + 1].path.is_none() {
+            crumbs.remove(i);
+        } else {
+            crumbs[i] = Crumb {
+                text: ELLIPSIS.to_string(),
+                path: None,
+            };
+        }
+    }
+    crumbs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn texts(crumbs: &[Crumb]) -> Vec<&str> {
+        crumbs.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    #[test]
+    fn crumbs_tests() {
+        let path = Path::new("/home/user/projects/onyx");
+        assert_eq!(
+            texts(&crumbs(path, None)),
+            ["/", "home", "user", "projects", "onyx"]
+        );
+
+        let home = crumbs(path, Some(Path::new("/home/user")));
+        assert_eq!(texts(&home), ["~", "projects", "onyx"]);
+        assert_eq!(
+            home[1].path.as_deref(),
+            Some(Path::new("/home/user/projects"))
+        );
+        assert_eq!(width(&home), "~/projects/onyx".len());
+
+        // a home of / would turn every path into ~
+        assert_eq!(texts(&crumbs(path, Some(Path::new("/")))).len(), 5);
+    }
+
+    #[test]
+    fn shorten_tests() {
+        let path = Path::new("/home/user/projects/onyx");
+        let short = shorten(crumbs(path, None), 16, None);
+        assert_eq!(texts(&short), ["/", "…", "projects", "onyx"]);
+        assert_eq!(short[1].path, None);
+
+        let keep = Path::new("/home");
+        let short = shorten(crumbs(path, None), 12, Some(keep));
+        assert_eq!(texts(&short), ["/", "home", "…", "onyx"]);
+
+        // never drops the first or last part
+        let short = shorten(crumbs(path, None), 1, None);
+        assert_eq!(texts(&short), ["/", "…", "onyx"]);
+    }
+}
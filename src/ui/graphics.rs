@@ -0,0 +1,323 @@
+use std::{
+    env,
+    io::{self, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::ValueEnum;
+use crossterm::{
+    cursor::{MoveTo, RestorePosition, SavePosition},
+    queue,
+};
+use image::{imageops, ImageFormat, Rgba, RgbaImage};
+use ratatui::{buffer::Buffer, layout::Rect, style::Color, widgets::Widget};
+
+// about how many pixels a cell has, they are around twice as high as wide
+const CELL_WIDTH: u32 = 10;
+const CELL_HEIGHT: u32 = 20;
+// kitty takes the data in chunks of this size
+const KITTY_CHUNK: usize = 4096;
+const KITTY_DELETE: &str = "\x1b_Ga=d,d=A,q=2\x1b\\";
+
+/// How images are drawn, `Auto` picks by the terminal
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, ValueEnum)]
+pub enum ImageProtocol {
+    #[default]
+    Auto,
+    Kitty,
+    Sixel,
+    Blocks,
+}
+
+impl ImageProtocol {
+    /// Resolves `Auto` by looking at which terminal this runs in
+    pub fn detect(self) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+        let var = |name| env::var(name).unwrap_or_default().to_lowercase();
+        let (term, program) = (var("TERM"), var("TERM_PROGRAM"));
+        if env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || ["wezterm", "ghostty"].contains(&program.as_str())
+        {
+            Self::Kitty
+        } else if term.contains("sixel")
+            || ["foot", "mlterm", "contour"]
+                .iter()
+                .any(|t| term.starts_with(t))
+        {
+            Self::Sixel
+        } else {
+            Self::Blocks
+        }
+    }
+}
+
+/// If `path` is an image that can be previewed
+pub fn is_image(path: &Path) -> bool {
+    matches!(
+        ImageFormat::from_path(path),
+        Ok(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP)
+    )
+}
+
+/// The cells an image of `width`x`height` pixels takes inside `area`, keeping its aspect ratio.
+/// Images are never scaled up
+pub fn fit(width: u32, height: u32, area: Rect) -> Rect {
+    if area.area() == 0 {
+        return area;
+    }
+    let max_width = f64::from(area.width) * f64::from(CELL_WIDTH);
+    let max_height = f64::from(area.height) * f64::from(CELL_HEIGHT);
+    let scale = (max_width / f64::from(width.max(1)))
+        .min(max_height / f64::from(height.max(1)))
+        .min(1.0);
+    let cells = |pixels: u32, cell: u32, max: u16| {
+        ((f64::from(pixels) * scale / f64::from(cell)).ceil() as u16).clamp(1, max)
+    };
+    Rect {
+        width: cells(width, CELL_WIDTH, area.width),
+        height: cells(height, CELL_HEIGHT, area.height),
+        ..area
+    }
+}
+
+/// Scales `image` to fit in `width`x`height` pixels
+fn scale(image: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    let scale = (f64::from(width) / f64::from(image.width()))
+        .min(f64::from(height) / f64::from(image.height()))
+        .min(1.0);
+    let width = ((f64::from(image.width()) * scale) as u32).max(1);
+    let height = ((f64::from(image.height()) * scale) as u32).max(1);
+    imageops::thumbnail(image, width, height)
+}
+
+fn color(pixel: &Rgba<u8>) -> Option<Color> {
+    let [r, g, b, a] = pixel.0;
+    (a >= 128).then_some(Color::Rgb(r, g, b))
+}
+
+/// Draws an image with half blocks, every cell shows two pixels one above the other
+pub struct HalfBlocks<'a>(pub &'a RgbaImage);
+
+impl Widget for HalfBlocks<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.area() == 0 {
+            return;
+        }
+        let image = scale(self.0, area.width.into(), u32::from(area.height) * 2);
+        let height = image.height().div_ceil(2).min(area.height.into());
+        for y in 0..height {
+            for x in 0..image.width().min(area.width.into()) {
+                let top = color(image.get_pixel(x, y * 2));
+                let bottom = (y * 2 + 1 < image.height())
+                    .then(|| color(image.get_pixel(x, y * 2 + 1)))
+                    .flatten();
+                let cell = buf.get_mut(area.x + x as u16, area.y + y as u16);
+                match (top, bottom) {
+                    (Some(top), bottom) => {
+                        cell.set_symbol("▀")
+                            .set_fg(top)
+                            .set_bg(bottom.unwrap_or(Color::Reset));
+                    }
+                    (None, Some(bottom)) => {
+                        cell.set_symbol("▄").set_fg(bottom).set_bg(Color::Reset);
+                    }
+                    (None, None) => {
+                        cell.set_symbol(" ");
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The escape codes that draw `image` over `area` with the kitty graphics protocol
+pub fn kitty(image: &RgbaImage, area: Rect) -> String {
+    let image = scale(
+        image,
+        u32::from(area.width) * CELL_WIDTH,
+        u32::from(area.height) * CELL_HEIGHT,
+    );
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    // writing to a vec can not fail
+    let _ = encoder.write_all(image.as_raw());
+    let data = STANDARD.encode(encoder.finish().unwrap_or_default());
+
+    let mut out = String::new();
+    let chunks = data.as_bytes().chunks(KITTY_CHUNK).collect::<Vec<_>>();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        let chunk = String::from_utf8_lossy(chunk);
+        if i == 0 {
+            // C=1 keeps the cursor where it is
+            out.push_str(&format!(
+                "\x1b_Ga=T,f=32,o=z,s={},v={},c={},r={},C=1,q=2,m={more};{chunk}\x1b\\",
+                image.width(),
+                image.height(),
+                area.width,
+                area.height
+            ));
+        } else {
+            out.push_str(&format!("\x1b_Gm={more};{chunk}\x1b\\"));
+        }
+    }
+    out
+}
+
+/// The escape codes that draw `image` over `area` as sixel, using a palette of 216 colors
+pub fn sixel(image: &RgbaImage, area: Rect) -> String {
+    let image = scale(
+        image,
+        u32::from(area.width) * CELL_WIDTH,
+        u32::from(area.height) * CELL_HEIGHT,
+    );
+    let (width, height) = image.dimensions();
+    // each channel gets 6 levels, transparent pixels are left out
+    let level = |c: u8| (u16::from(c) * 5 + 127) / 255;
+    let indices = image
+        .pixels()
+        .map(|p| {
+            let [r, g, b, a] = p.0;
+            (a >= 128).then(|| (level(r) * 36 + level(g) * 6 + level(b)) as usize)
+        })
+        .collect::<Vec<_>>();
+
+    // 1 as the second parameter keeps the background of pixels that are not set
+    let mut out = format!("\x1bP0;1;0q\"1;1;{width};{height}");
+    for i in 0..216 {
+        let percent = |level: usize| level * 100 / 5;
+        out.push_str(&format!(
+            "#{i};2;{};{};{}",
+            percent(i / 36),
+            percent(i / 6 % 6),
+            percent(i % 6)
+        ));
+    }
+    for band in (0..height).step_by(6) {
+        // the six pixels of each column, for every color used in the band
+        let mut colors: Vec<Option<Vec<u8>>> = vec![None; 216];
+        for y in band..(band + 6).min(height) {
+            for x in 0..width {
+                if let Some(i) = indices[(y * width + x) as usize] {
+                    colors[i].get_or_insert_with(|| vec![0; width as usize])[x as usize] |=
+                        1 << (y - band);
+                }
+            }
+        }
+        for (color, sixels) in colors.iter().enumerate() {
+            let Some(sixels) = sixels else {
+                continue;
+            };
+            out.push_str(&format!("#{color}"));
+            for run in sixels.chunk_by(|a, b| a == b) {
+                let c = char::from(63 + run[0]);
+                match run.len() {
+                    n @ 1..=3 => out.extend(std::iter::repeat_n(c, n)),
+                    n => out.push_str(&format!("!{n}{c}")),
+                }
+            }
+            // back to the start of the band for the next color
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+/// An image drawn with escape codes, outside of the buffer ratatui knows about
+#[derive(Clone)]
+pub struct Graphic {
+    pub area: Rect,
+    pub protocol: ImageProtocol,
+    pub data: Arc<str>,
+}
+
+impl PartialEq for Graphic {
+    fn eq(&self, other: &Self) -> bool {
+        self.area == other.area && Arc::ptr_eq(&self.data, &other.data)
+    }
+}
+
+impl Graphic {
+    /// Writes it at its place, `shown` is the graphic currently on screen which is removed first
+    pub fn show(
+        graphic: Option<&Self>,
+        shown: Option<&Self>,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        if shown.is_some_and(|s| s.protocol == ImageProtocol::Kitty) {
+            out.write_all(KITTY_DELETE.as_bytes())?;
+        }
+        if let Some(graphic) = graphic {
+            queue!(out, SavePosition, MoveTo(graphic.area.x, graphic.area.y))?;
+            out.write_all(graphic.data.as_bytes())?;
+            queue!(out, RestorePosition)?;
+        }
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    use super::*;
+
+    #[test]
+    fn half_blocks_tests() {
+        let red = Rgba([255, 0, 0, 255]);
+        let blue = Rgba([0, 0, 255, 255]);
+        let clear = Rgba([0, 0, 0, 0]);
+        let image = RgbaImage::from_fn(2, 4, |x, y| match (x, y) {
+            (_, 0) => red,
+            (_, 1) => blue,
+            (0, _) => clear,
+            _ => red,
+        });
+
+        let mut terminal = Terminal::new(TestBackend::new(4, 3)).unwrap();
+        terminal
+            .draw(|f| f.render_widget(HalfBlocks(&image), f.size()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+
+        let cell = buffer.get(0, 0);
+        assert_eq!(
+            (cell.symbol.as_str(), cell.fg, cell.bg),
+            ("▀", Color::Rgb(255, 0, 0), Color::Rgb(0, 0, 255))
+        );
+        assert_eq!(buffer.get(0, 1).symbol, " ");
+        let cell = buffer.get(1, 1);
+        assert_eq!(
+            (cell.symbol.as_str(), cell.fg),
+            ("▀", Color::Rgb(255, 0, 0))
+        );
+        // never scaled up
+        assert_eq!(buffer.get(2, 0).symbol, " ");
+        assert_eq!(buffer.get(0, 2).symbol, " ");
+
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 4, 3));
+        HalfBlocks(&image).render(Rect::new(1, 1, 0, 2), &mut buffer);
+        HalfBlocks(&image).render(Rect::new(4, 0, 0, 3), &mut buffer);
+        assert_eq!(buffer, Buffer::empty(Rect::new(0, 0, 4, 3)));
+    }
+
+    #[test]
+    fn fit_tests() {
+        let area = Rect::new(2, 3, 40, 10);
+        assert_eq!(fit(100, 100, area), Rect::new(2, 3, 10, 5));
+        // wide images are limited by the width
+        assert_eq!(fit(8000, 1000, area), Rect::new(2, 3, 40, 3));
+        assert_eq!(fit(1000, 8000, area), Rect::new(2, 3, 3, 10));
+        assert_eq!(
+            fit(100, 100, Rect::new(2, 3, 0, 10)),
+            Rect::new(2, 3, 0, 10)
+        );
+        assert!(sixel(&RgbaImage::new(3, 7), area).starts_with("\x1bP"));
+    }
+}
@@ -6,6 +6,41 @@ use crate::Mode;
 
 pub fn match_keycode(mode: &Mode, current_file: Option<PathBuf>, input: KeyCode) -> InputResult {
     match input {
+        KeyCode::Up if mode.get_list_selected().is_some() => {
+            InputResult::Mode(InputModeResult::MoveUp)
+        }
+        KeyCode::Down if mode.get_list_selected().is_some() => {
+            InputResult::Mode(InputModeResult::MoveDown)
+        }
+        // lists with an input need the letters for typing
+        KeyCode::Char('k') if mode.get_list_selected().is_some() && mode.get_str().is_none() => {
+            InputResult::Mode(InputModeResult::MoveUp)
+        }
+        KeyCode::Char('j') if mode.get_list_selected().is_some() && mode.get_str().is_none() => {
+            InputResult::Mode(InputModeResult::MoveDown)
+        }
+        KeyCode::Char('d') if matches!(mode, Mode::Bookmarks(_)) => {
+            InputResult::Bookmark(BookmarkAction::Delete)
+        }
+        KeyCode::Char('r') if matches!(mode, Mode::Bookmarks(_)) => {
+            InputResult::Bookmark(BookmarkAction::Rename)
+        }
+        KeyCode::Char('q') if mode.get_list_selected().is_some() && mode.get_str().is_none() => {
+            InputResult::Mode(InputModeResult::ModeChange(Mode::Basic))
+        }
+        KeyCode::Tab if matches!(mode, Mode::GoTo(_)) => {
+            InputResult::Mode(InputModeResult::Complete)
+        }
+        // going left in the path bar goes further up
+        KeyCode::Left | KeyCode::Char('h') if matches!(mode, Mode::Ancestor(_)) => {
+            InputResult::Mode(InputModeResult::MoveDown)
+        }
+        KeyCode::Right | KeyCode::Char('l') if matches!(mode, Mode::Ancestor(_)) => {
+            InputResult::Mode(InputModeResult::MoveUp)
+        }
+        KeyCode::Char('q') if matches!(mode, Mode::Ancestor(_)) => {
+            InputResult::Mode(InputModeResult::ModeChange(Mode::Basic))
+        }
         KeyCode::Char(c) if mode != &Mode::Basic => InputResult::Mode(InputModeResult::AddChar(c)),
         KeyCode::Backspace if mode != &Mode::Basic => {
             InputResult::Mode(InputModeResult::RemoveChar)
@@ -19,6 +54,81 @@ pub fn match_keycode(mode: &Mode, current_file: Option<PathBuf>, input: KeyCode)
         KeyCode::Left | KeyCode::Char('h') => InputResult::GoBack,
         KeyCode::Right | KeyCode::Char('l') => InputResult::EnterFolder,
         KeyCode::Char('q') => InputResult::Quit,
+        KeyCode::Char('H') => InputResult::Back,
+        KeyCode::Char('L') => InputResult::Forward,
+        KeyCode::Char('R') => InputResult::Mode(InputModeResult::ModeChange(Mode::History(0))),
+        KeyCode::Char('z') => {
+            InputResult::Mode(InputModeResult::ModeChange(Mode::Jump(String::new(), 0)))
+        }
+        KeyCode::Char(':') => {
+            InputResult::Mode(InputModeResult::ModeChange(Mode::GoTo(String::new())))
+        }
+        KeyCode::Char('P') => InputResult::Mode(InputModeResult::ModeChange(Mode::Ancestor(1))),
+        KeyCode::Char('m') => InputResult::Mode(InputModeResult::ModeChange(Mode::SetMark)),
+        KeyCode::Char('\'') => InputResult::Mode(InputModeResult::ModeChange(Mode::GotoMark)),
+        KeyCode::Char('b') => InputResult::Mode(InputModeResult::ModeChange(Mode::Bookmarks(0))),
+        KeyCode::Char('B') => InputResult::Mode(InputModeResult::ModeChange(Mode::AddBookmark(
+            String::new(),
+        ))),
+        KeyCode::Enter => InputResult::Confirm,
+        KeyCode::Char(' ') => InputResult::ToggleMark,
+        KeyCode::Char('t') => InputResult::Tab(TabAction::New),
+        KeyCode::Char('T') => InputResult::Tab(TabAction::Close),
+        KeyCode::Tab if mode == &Mode::Basic => InputResult::Tab(TabAction::Next),
+        KeyCode::BackTab if mode == &Mode::Basic => InputResult::Tab(TabAction::Prev),
+        KeyCode::Char('<') => InputResult::Tab(TabAction::MoveLeft),
+        KeyCode::Char('>') => InputResult::Tab(TabAction::MoveRight),
+        KeyCode::Char('v') => InputResult::Pane(PaneAction::CycleLayout),
+        KeyCode::Char('w') => InputResult::Pane(PaneAction::SwapFocus),
+        KeyCode::Char('=') => InputResult::Pane(PaneAction::Sync),
+        KeyCode::Char('J') => InputResult::Preview(PreviewAction::Scroll {
+            amount: ScrollAmount::Lines(1),
+            up: false,
+        }),
+        KeyCode::Char('K') => InputResult::Preview(PreviewAction::Scroll {
+            amount: ScrollAmount::Lines(1),
+            up: true,
+        }),
+        KeyCode::Char('}') => InputResult::Preview(PreviewAction::Scroll {
+            amount: ScrollAmount::HalfPage,
+            up: false,
+        }),
+        KeyCode::Char('{') => InputResult::Preview(PreviewAction::Scroll {
+            amount: ScrollAmount::HalfPage,
+            up: true,
+        }),
+        KeyCode::PageDown => InputResult::Preview(PreviewAction::Scroll {
+            amount: ScrollAmount::Page,
+            up: false,
+        }),
+        KeyCode::PageUp => InputResult::Preview(PreviewAction::Scroll {
+            amount: ScrollAmount::Page,
+            up: true,
+        }),
+        KeyCode::Char('#') => InputResult::Preview(PreviewAction::ToggleLineNumbers),
+        KeyCode::Char('W') => InputResult::Preview(PreviewAction::ToggleWrap),
+        KeyCode::Char('f') => InputResult::Preview(PreviewAction::ToggleFullscreen),
+        KeyCode::Char('F') => InputResult::Preview(PreviewAction::ToggleTail),
+        KeyCode::Char('M') => InputResult::Preview(PreviewAction::ToggleMarkdown),
+        KeyCode::Char('D') => InputResult::Preview(PreviewAction::ToggleDiff),
+        KeyCode::Char('G') => InputResult::Preview(PreviewAction::ToggleBlame),
+        KeyCode::Char('+') => InputResult::Git(GitAction::Stage),
+        KeyCode::Char('-') => InputResult::Git(GitAction::Unstage),
+        KeyCode::Char('!') => InputResult::Git(GitAction::Discard),
+        KeyCode::Char('e') => {
+            InputResult::Mode(InputModeResult::ModeChange(Mode::Encoding(String::new())))
+        }
+        KeyCode::Char('o') => {
+            InputResult::Mode(InputModeResult::ModeChange(Mode::HexOffset(String::new())))
+        }
+        KeyCode::Char('y') => InputResult::Copy,
+        KeyCode::Char('x') => InputResult::Move,
+        KeyCode::Char('C') => InputResult::Compress,
+        KeyCode::Char('X') => InputResult::Extract { here: true },
+        KeyCode::Char('E') => InputResult::Extract { here: false },
+        KeyCode::Char('.') => InputResult::ToggleHidden,
+        KeyCode::Char('s') => InputResult::CycleSort,
+        KeyCode::Char('S') => InputResult::ReverseSort,
         KeyCode::Char('c') => {
             InputResult::Mode(InputModeResult::ModeChange(Mode::CreateFile(String::new())))
         }
@@ -45,6 +155,43 @@ pub enum InputResult {
     Mode(InputModeResult),
     EnterFolder,
     GoBack,
+    Back,
+    Forward,
+
+    ToggleMark,
+    Confirm,
+
+    ToggleHidden,
+    CycleSort,
+    ReverseSort,
+
+    Tab(TabAction),
+    Pane(PaneAction),
+    Preview(PreviewAction),
+    Copy,
+    Move,
+    Compress,
+    // into the current directory, or a folder named after the archive
+    Extract {
+        here: bool,
+    },
+    Bookmark(BookmarkAction),
+    Git(GitAction),
+
+    // mouse
+    Click {
+        tab: usize,
+        index: usize,
+        double: bool,
+    },
+    Scroll {
+        tab: usize,
+        up: bool,
+    },
+    GoTo {
+        tab: usize,
+        path: PathBuf,
+    },
 
     Quit,
     Skip,
@@ -54,5 +201,54 @@ pub enum InputModeResult {
     ModeChange(Mode),
     AddChar(char),
     RemoveChar,
+    MoveUp,
+    MoveDown,
+    Complete,
+    Select(usize),
     Execute,
 }
+
+pub enum TabAction {
+    New,
+    Close,
+    Next,
+    Prev,
+    MoveLeft,
+    MoveRight,
+    Select(usize),
+}
+
+// on the marked entries, or the selected one
+pub enum GitAction {
+    Stage,
+    Unstage,
+    Discard,
+}
+
+pub enum PaneAction {
+    CycleLayout,
+    SwapFocus,
+    Sync,
+}
+
+pub enum BookmarkAction {
+    Delete,
+    Rename,
+}
+
+pub enum PreviewAction {
+    Scroll { amount: ScrollAmount, up: bool },
+    ToggleLineNumbers,
+    ToggleWrap,
+    ToggleFullscreen,
+    ToggleTail,
+    ToggleMarkdown,
+    ToggleDiff,
+    ToggleBlame,
+}
+
+pub enum ScrollAmount {
+    Lines(usize),
+    HalfPage,
+    Page,
+}
@@ -0,0 +1,405 @@
+use std::path::Path;
+
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+use super::preview::highlight_code;
+
+const RULE_WIDTH: usize = 40;
+
+/// If `path` is a Markdown file by its extension
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| e == "md" || e == "markdown")
+}
+
+/// A table that is shown once all cells are known, so the columns can be lined up
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+}
+
+#[derive(Default)]
+struct Renderer {
+    lines: Vec<Line<'static>>,
+    spans: Vec<Span<'static>>,
+    styles: Vec<Style>,
+    // the next number of ordered lists, `None` for bullet lists
+    lists: Vec<Option<u64>>,
+    // shown before the first line of a list item
+    marker: Option<String>,
+    marker_width: usize,
+    quotes: usize,
+    // the language and content of the code block being read
+    code: Option<(String, String)>,
+    table: Option<Table>,
+    links: Vec<String>,
+}
+
+impl Renderer {
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_default()
+    }
+
+    fn push_style(&mut self, style: Style) {
+        self.styles.push(self.style().patch(style));
+    }
+
+    // what goes before each line, the bars of quotes and the indent of lists
+    fn prefix(&mut self) -> Vec<Span<'static>> {
+        let mut prefix = vec![];
+        if self.quotes > 0 {
+            prefix.push(Span::styled(
+                "│ ".repeat(self.quotes),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        if !self.lists.is_empty() {
+            prefix.push(Span::raw("  ".repeat(self.lists.len() - 1)));
+            prefix.push(match self.marker.take() {
+                Some(marker) => Span::styled(marker, Style::default().fg(Color::Yellow)),
+                None => Span::raw(" ".repeat(self.marker_width)),
+            });
+        }
+        prefix
+    }
+
+    fn flush(&mut self) {
+        if self.spans.is_empty() && self.marker.is_none() {
+            return;
+        }
+        let mut spans = self.prefix();
+        spans.append(&mut self.spans);
+        self.lines.push(Line::from(spans));
+    }
+
+    // an empty line between blocks, but never two in a row
+    fn blank(&mut self) {
+        if self.lines.last().is_some_and(|l| l.width() > 0) {
+            let prefix = match self.quotes {
+                0 => vec![],
+                _ => vec![Span::styled(
+                    "│ ".repeat(self.quotes),
+                    Style::default().fg(Color::DarkGray),
+                )],
+            };
+            self.lines.push(Line::from(prefix));
+        }
+    }
+
+    fn text(&mut self, span: Span<'static>) {
+        match (&mut self.code, &mut self.table) {
+            (Some((_, code)), _) => code.push_str(&span.content),
+            (_, Some(table)) => {
+                if let Some(cell) = table.rows.last_mut().and_then(|r| r.last_mut()) {
+                    cell.push(span);
+                }
+            }
+            _ => self.spans.push(span),
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush();
+                let style = Style::default().add_modifier(Modifier::BOLD);
+                self.push_style(match level {
+                    HeadingLevel::H1 => style.fg(Color::Cyan).add_modifier(Modifier::UNDERLINED),
+                    HeadingLevel::H2 => style.fg(Color::Cyan),
+                    _ => style.fg(Color::Blue),
+                });
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.quotes += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush();
+                let lang = match kind {
+                    CodeBlockKind::Fenced(lang) => {
+                        lang.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((lang, String::new()));
+            }
+            Tag::List(start) => {
+                self.flush();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.marker_width = marker.chars().count();
+                self.marker = Some(marker);
+            }
+            Tag::Table(alignments) => {
+                self.flush();
+                self.table = Some(Table {
+                    alignments,
+                    rows: vec![],
+                });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(vec![]);
+                }
+            }
+            Tag::TableCell => {
+                if let Some(row) = self.table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    row.push(vec![]);
+                }
+            }
+            Tag::Emphasis => self.push_style(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.push_style(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => {
+                self.push_style(Style::default().add_modifier(Modifier::CROSSED_OUT))
+            }
+            Tag::Link { dest_url, .. } => {
+                self.links.push(dest_url.to_string());
+                self.push_style(
+                    Style::default()
+                        .fg(Color::Blue)
+                        .add_modifier(Modifier::UNDERLINED),
+                );
+            }
+            Tag::Image { .. } => {
+                self.push_style(Style::default().fg(Color::DarkGray));
+                self.text(Span::styled("[image: ", self.style()));
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        let dim = Style::default().fg(Color::DarkGray);
+        match tag {
+            TagEnd::Paragraph => {
+                self.flush();
+                self.blank();
+            }
+            TagEnd::Heading(_) => {
+                self.styles.pop();
+                self.flush();
+                self.blank();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quotes -= 1;
+                // the blank line still belongs to the quote
+                if self
+                    .lines
+                    .last()
+                    .is_some_and(|l| l.width() <= 2 * (self.quotes + 1))
+                {
+                    self.lines.pop();
+                }
+                self.blank();
+            }
+            TagEnd::CodeBlock => {
+                let Some((lang, code)) = self.code.take() else {
+                    return;
+                };
+                for line in highlight_code(&lang, &code) {
+                    let mut spans = self.prefix();
+                    spans.push(Span::styled("  ", dim));
+                    spans.extend(line.spans.into_iter().map(|mut s| {
+                        s.content = s.content.trim_end_matches(['\n', '\r']).to_string().into();
+                        s
+                    }));
+                    self.lines.push(Line::from(spans));
+                }
+                self.blank();
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank();
+                }
+            }
+            TagEnd::Item => self.flush(),
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.table(table);
+                }
+                self.blank();
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => {
+                self.styles.pop();
+            }
+            TagEnd::Link => {
+                self.styles.pop();
+                let url = self.links.pop().unwrap_or_default();
+                // links within the document are not worth showing
+                if !url.is_empty() && !url.starts_with('#') {
+                    self.text(Span::styled(format!(" ({url})"), dim));
+                }
+            }
+            TagEnd::Image => {
+                self.text(Span::styled("]", self.style()));
+                self.styles.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn table(&mut self, table: Table) {
+        let dim = Style::default().fg(Color::DarkGray);
+        let columns = table.rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        let widths = (0..columns)
+            .map(|c| {
+                table
+                    .rows
+                    .iter()
+                    .filter_map(|r| r.get(c))
+                    .map(|cell| width(cell))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        for (i, row) in table.rows.into_iter().enumerate() {
+            let mut spans = self.prefix();
+            for (c, column_width) in widths.iter().enumerate() {
+                if c > 0 {
+                    spans.push(Span::styled(" │ ", dim));
+                }
+                let cell = row.get(c).cloned().unwrap_or_default();
+                let pad = column_width.saturating_sub(width(&cell));
+                let (left, right) = match table.alignments.get(c) {
+                    Some(Alignment::Right) => (pad, 0),
+                    Some(Alignment::Center) => (pad / 2, pad - pad / 2),
+                    _ => (0, pad),
+                };
+                spans.push(Span::raw(" ".repeat(left)));
+                spans.extend(cell.into_iter().map(|mut s| {
+                    // the header is bold
+                    if i == 0 {
+                        s.style = s.style.add_modifier(Modifier::BOLD);
+                    }
+                    s
+                }));
+                spans.push(Span::raw(" ".repeat(right)));
+            }
+            self.lines.push(Line::from(spans));
+
+            if i == 0 {
+                let rule = widths
+                    .iter()
+                    .map(|w| "─".repeat(*w))
+                    .collect::<Vec<_>>()
+                    .join("─┼─");
+                let mut spans = self.prefix();
+                spans.push(Span::styled(rule, dim));
+                self.lines.push(Line::from(spans));
+            }
+        }
+    }
+}
+
+fn width(spans: &[Span]) -> usize {
+    spans.iter().map(|s| s.width()).sum()
+}
+
+/// Renders Markdown into styled lines, code blocks are highlighted like files are
+pub fn render(text: &str) -> Vec<Line<'static>> {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut r = Renderer::default();
+    for event in Parser::new_ext(text, options) {
+        match event {
+            Event::Start(tag) => r.start(tag),
+            Event::End(tag) => r.end(tag),
+            Event::Text(text) => r.text(Span::styled(text.to_string(), r.style())),
+            Event::Code(code) => {
+                r.text(Span::styled(code.to_string(), r.style().fg(Color::Yellow)))
+            }
+            Event::Html(html) | Event::InlineHtml(html) => r.text(Span::styled(
+                html.trim_end().to_string(),
+                Style::default().fg(Color::DarkGray),
+            )),
+            Event::SoftBreak => r.text(Span::raw(" ")),
+            Event::HardBreak => r.flush(),
+            Event::Rule => {
+                r.flush();
+                let rule =
+                    Span::styled("─".repeat(RULE_WIDTH), Style::default().fg(Color::DarkGray));
+                let mut spans = r.prefix();
+                spans.push(rule);
+                r.lines.push(Line::from(spans));
+                r.blank();
+            }
+            Event::TaskListMarker(done) => {
+                r.text(Span::styled(
+                    if done { "[x] " } else { "[ ] " },
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+            _ => {}
+        }
+    }
+    r.flush();
+    while r.lines.last().is_some_and(|l| l.width() == 0) {
+        r.lines.pop();
+    }
+    r.lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn texts(lines: &[Line]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn render_tests() {
+        let lines = render("# Title\n\nSome *text* and `code`.\n\n- a\n- b\n  1. c\n\n> quoted\n");
+        assert_eq!(
+            texts(&lines),
+            [
+                "Title",
+                "",
+                "Some text and code.",
+                "",
+                "• a",
+                "• b",
+                "  1. c",
+                "",
+                "│ quoted"
+            ]
+        );
+        assert!(lines[0].spans[0]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+        assert!(lines[2].spans[1]
+            .style
+            .add_modifier
+            .contains(Modifier::ITALIC));
+
+        let lines = render("| a | long |\n|--:|---|\n| 1 | 2 |\n\n```rust\nfn main() {}\n```\n");
+        assert_eq!(
+            texts(&lines),
+            ["a │ long", "──┼─────", "1 │ 2   ", "", "  fn main() {}"]
+        );
+        // highlighted, so not a single span
+        assert!(lines[4].spans.len() > 2);
+    }
+}
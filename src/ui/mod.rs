@@ -1,5 +1,11 @@
-use std::{fs, io};
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{PathBuf, MAIN_SEPARATOR},
+    time::Instant,
+};
 
+use chrono::Local;
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture, Event, KeyEventKind},
     execute,
@@ -8,33 +14,58 @@ use crossterm::{
 use eyre::{eyre, Context, ContextCompat, Result};
 use ratatui::{
     prelude::{Backend, Constraint, CrosstermBackend, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
     Frame, Terminal,
 };
-use syntect::{
-    easy::HighlightLines, highlighting::ThemeSet, parsing::SyntaxSet, util::LinesWithEndings,
-};
 
-use crate::{state::InfoKind, Mode, State};
+use crate::{
+    bookmarks::Bookmarks,
+    filesystem::{path, read::FileKind, utils::format_size},
+    frecency::{Frecency, JUMP_MATCHES},
+    jobs::Job,
+    pick::Picker,
+    shell,
+    state::{InfoKind, PaneLayout, Panes},
+    Mode, State,
+};
 
-use self::utils::convert_sytax_style;
+use self::{
+    mouse::{Areas, ListArea},
+    preview::Preview,
+};
 
+mod ansi;
+mod breadcrumb;
+pub mod graphics;
 pub mod input;
+mod markdown;
+pub mod mouse;
+mod preview;
 mod utils;
 
 const UI_ERROR_WRAP: &str = "Error while rendering ui:";
 
-pub fn make_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
+/// Where the ui is drawn
+pub type Output = Box<dyn Write>;
+
+/// Draws on stdout, or on the terminal itself when stdout is kept for what is printed on exit
+pub fn make_terminal(keep_stdout: bool) -> Result<Terminal<CrosstermBackend<Output>>> {
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    let mut out: Output = match keep_stdout {
+        true => match OpenOptions::new().write(true).open("/dev/tty") {
+            Ok(tty) => Box::new(BufWriter::new(tty)),
+            Err(_) => Box::new(io::stderr()),
+        },
+        false => Box::new(io::stdout()),
+    };
+    execute!(out, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(out);
     Ok(Terminal::new(backend)?)
 }
 
-pub fn restore_terminal(mut terminal: Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+pub fn restore_terminal(mut terminal: Terminal<CrosstermBackend<Output>>) -> Result<()> {
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
@@ -45,32 +76,130 @@ pub fn restore_terminal(mut terminal: Terminal<CrosstermBackend<io::Stdout>>) ->
     Ok(())
 }
 
+/// Everything outside of the ui that is needed to draw it
+pub struct DrawContext<'a> {
+    pub tabs: &'a [State],
+    pub panes: &'a Panes,
+    pub pick: Option<&'a Picker>,
+    pub bookmarks: &'a Bookmarks,
+    pub frecency: &'a Frecency,
+    pub jobs: &'a [Job],
+}
+
+#[derive(Default)]
 pub struct UiState {
-    pub scroll_state: ListState,
+    // one for each pane
+    pub scroll_states: [ListState; 2],
+    parent_scroll: ListState,
+    areas: Areas,
+    last_click: Option<(Instant, u16, u16)>,
+    // the go to candidates with the directory and input they were read for
+    completions: Option<((PathBuf, String), Vec<String>)>,
+    pub preview: Preview,
 }
 
 impl UiState {
-    pub async fn input(&self, input: Event, state: &State) -> input::InputResult {
-        if let Event::Key(key_event) = input {
-            if key_event.kind == KeyEventKind::Release {
-                return input::InputResult::Skip;
+    pub async fn input(&mut self, input: Event, state: &State) -> input::InputResult {
+        match input {
+            Event::Key(key_event) => {
+                if key_event.kind == KeyEventKind::Release {
+                    return input::InputResult::Skip;
+                }
+                input::match_keycode(
+                    &state.mode,
+                    state.files.get(state.selected).map(|f| f.path.clone()),
+                    key_event.code,
+                )
             }
-            return input::match_keycode(
-                &state.mode,
-                state.files.get(state.selected).map(|f| f.path.clone()),
-                key_event.code,
-            );
+            Event::Mouse(mouse_event) => {
+                mouse::match_mouse(mouse_event, &self.areas, state, &mut self.last_click)
+            }
+            _ => input::InputResult::Skip,
         }
-        input::InputResult::Skip
     }
 
-    pub fn draw(&mut self, f: &mut Frame<'_, impl Backend>, state: &State) {
+    pub fn draw(&mut self, f: &mut Frame<'_, impl Backend>, ctx: &DrawContext) {
+        let DrawContext { tabs, panes, .. } = *ctx;
+        let state = &tabs[panes.current];
+        self.areas = Areas::default();
+        self.preview.graphic = None;
+        self.preview.covered = !matches!(state.mode, Mode::Basic | Mode::Ancestor(_));
+        let root = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(0)
+            .constraints(
+                [
+                    // only show the tab bar when there is more than one tab
+                    Constraint::Length((tabs.len() > 1).into()),
+                    Constraint::Min(0),
+                ]
+                .as_ref(),
+            )
+            .split(f.size());
         let layout = Layout::default()
             .direction(Direction::Horizontal)
             .margin(0)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-            .split(f.size());
-        let left_layout = Layout::default()
+            .split(root[1]);
+
+        self.draw_tabs(f, root[0], tabs, panes);
+        if self.preview.fullscreen {
+            self.draw_content(f, root[1], state);
+            self.draw_input(f, state, ctx);
+            return;
+        }
+        match panes.layout {
+            PaneLayout::Single => {
+                self.draw_pane(f, layout[0], 0, panes.current, ctx);
+                self.draw_content(f, layout[1], state);
+            }
+            PaneLayout::Dual => {
+                // the panes keep the order of their tabs
+                let left = panes.current.min(panes.other);
+                let right = panes.current.max(panes.other);
+                self.draw_pane(f, layout[0], 0, left, ctx);
+
+                let border = Block::default().borders(Borders::LEFT);
+                let chunk = border.inner(layout[1]);
+                f.render_widget(border, layout[1]);
+                self.draw_pane(f, chunk, 1, right, ctx);
+            }
+            PaneLayout::Miller => {
+                let layout = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .margin(0)
+                    .constraints(
+                        [
+                            Constraint::Percentage(20),
+                            Constraint::Percentage(35),
+                            Constraint::Percentage(45),
+                        ]
+                        .as_ref(),
+                    )
+                    .split(root[1]);
+
+                let border = Block::default().borders(Borders::RIGHT);
+                let chunk = border.inner(layout[0]);
+                f.render_widget(border, layout[0]);
+                self.draw_parent(f, chunk, panes.current, state);
+                self.draw_pane(f, layout[1], 0, panes.current, ctx);
+                self.draw_content(f, layout[2], state);
+            }
+        }
+        self.draw_input(f, state, ctx);
+    }
+
+    fn draw_pane(
+        &mut self,
+        f: &mut Frame<'_, impl Backend>,
+        chunk: Rect,
+        pane: usize,
+        tab: usize,
+        ctx: &DrawContext,
+    ) {
+        let state = &ctx.tabs[tab];
+        let active = tab == ctx.panes.current;
+        let layout = Layout::default()
             .direction(Direction::Vertical)
             .margin(0)
             .constraints(
@@ -81,31 +210,141 @@ impl UiState {
                 ]
                 .as_ref(),
             )
-            .split(layout[0]);
-
-        self.draw_path(
-            f,
-            left_layout[0],
-            state.path.to_str().wrap_err(UI_ERROR_WRAP).unwrap(),
-        );
-        self.draw_files(f, left_layout[1], state)
+            .split(chunk);
+
+        self.draw_path(f, layout[0], tab, state, active);
+        self.draw_files(f, layout[1], pane, tab, state, ctx.pick)
             .wrap_err(UI_ERROR_WRAP)
             .unwrap();
-        self.draw_info(f, left_layout[2], state);
-        self.draw_content(f, layout[1], state);
-        self.draw_input(f, state);
+        let jobs = if active { ctx.jobs } else { &[] };
+        self.draw_info(f, layout[2], state, jobs);
     }
 
-    fn draw_path(&mut self, f: &mut Frame<'_, impl Backend>, chunk: Rect, path: &str) {
-        // Remove some windows stuff
-        f.render_widget(Paragraph::new(path.replace("\\\\?\\", "")), chunk);
+    fn draw_tabs(
+        &mut self,
+        f: &mut Frame<'_, impl Backend>,
+        chunk: Rect,
+        tabs: &[State],
+        panes: &Panes,
+    ) {
+        let spans = tabs
+            .iter()
+            .enumerate()
+            .map(|(i, tab)| {
+                let name = tab
+                    .path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| shell::display_path(&tab.path));
+                let style = if i == panes.current {
+                    Style::default().fg(Color::Black).bg(Color::Cyan)
+                } else if panes.layout == PaneLayout::Dual && i == panes.other {
+                    Style::default().fg(Color::Black).bg(Color::Gray)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                Span::styled(format!(" {}:{name} ", i + 1), style)
+            })
+            .collect::<Vec<_>>();
+
+        let mut x = chunk.x;
+        for (i, span) in spans.iter().enumerate() {
+            let width = (span.width() as u16).min(chunk.right().saturating_sub(x));
+            self.areas.tabs.push((Rect { x, width, ..chunk }, i));
+            x += width;
+        }
+        f.render_widget(Paragraph::new(Line::from(spans)), chunk);
+    }
+
+    fn draw_path(
+        &mut self,
+        f: &mut Frame<'_, impl Backend>,
+        chunk: Rect,
+        tab: usize,
+        state: &State,
+        active: bool,
+    ) {
+        let dim = Style::default().fg(Color::DarkGray);
+        let ancestor = match state.mode {
+            Mode::Ancestor(i) if active => state.path.ancestors().nth(i),
+            _ => None,
+        };
+
+        let status = match (&state.branch, state.free_space) {
+            _ if ancestor.is_some() => vec![Span::styled(
+                " h/l: choose, enter: go ",
+                Style::default().fg(Color::Yellow),
+            )],
+            (branch, free) => {
+                let mut spans = vec![];
+                if let Some(branch) = branch {
+                    spans.push(Span::styled(
+                        format!(" {branch}"),
+                        Style::default().fg(Color::Magenta),
+                    ));
+                }
+                if let Some(free) = free {
+                    spans.push(Span::styled(format!(" {} free ", format_size(free)), dim));
+                }
+                spans
+            }
+        };
+        let status = Line::from(status);
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(status.width() as u16),
+            ])
+            .split(chunk);
+        f.render_widget(Paragraph::new(status), layout[1]);
+
+        // the ancestor being chosen has to stay visible, so ~ is only used below it
+        let home = dirs::home_dir().filter(|h| ancestor.is_none_or(|a| a.starts_with(h)));
+        let crumbs = breadcrumb::crumbs(&state.path, home.as_deref());
+        let crumbs = breadcrumb::shorten(crumbs, layout[0].width as usize, ancestor);
+
+        // every part of the path can be clicked to go there
+        let chunk = layout[0];
+        let mut spans = vec![];
+        let mut x = chunk.x;
+        for (i, crumb) in crumbs.iter().enumerate() {
+            if i > 0 && crumbs[i - 1].needs_separator() {
+                spans.push(Span::styled(MAIN_SEPARATOR.to_string(), dim));
+                x += 1;
+            }
+            let style = if !active {
+                dim
+            } else if crumb.path.is_some() && crumb.path.as_deref() == ancestor {
+                Style::default().fg(Color::Black).bg(Color::Yellow)
+            } else if i == crumbs.len() - 1 {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else if crumb.path.is_none() {
+                dim
+            } else {
+                Style::default().fg(Color::Blue)
+            };
+            let span = Span::styled(crumb.text.as_str(), style);
+            let width = (span.width() as u16).min(chunk.right().saturating_sub(x));
+            if let Some(path) = &crumb.path {
+                self.areas
+                    .path
+                    .push((Rect { x, width, ..chunk }, tab, path.clone()));
+            }
+            x += width;
+            spans.push(span);
+        }
+        f.render_widget(Paragraph::new(Line::from(spans)), chunk);
     }
 
     fn draw_files(
         &mut self,
         f: &mut Frame<'_, impl Backend>,
         chunk: Rect,
+        pane: usize,
+        tab: usize,
         state: &State,
+        pick: Option<&Picker>,
     ) -> Result<()> {
         let mut items = state
             .files
@@ -119,60 +358,268 @@ impl UiState {
                     )
                 })?;
 
+                let color = if state.marked.contains(&file.path) {
+                    Color::Yellow
+                } else if pick.is_some_and(|p| !p.accepts(file)) {
+                    Color::DarkGray
+                } else {
+                    utils::file_color(file.kind)
+                };
                 let style = if pos == state.selected {
-                    Style::default()
-                        .fg(Color::Black)
-                        .bg(self.get_file_color(&file.file_type)?)
+                    Style::default().fg(Color::Black).bg(color)
                 } else {
-                    Style::default().fg(self.get_file_color(&file.file_type)?)
+                    Style::default().fg(color)
+                };
+                // the names stay lined up, clean files get an empty marker
+                let line = match &state.git.status {
+                    Some(_) if !state.in_archive() => {
+                        let marker = match state.git.get(file) {
+                            // the selected row already has its own colors
+                            Some(status) if pos == state.selected => {
+                                Span::raw(format!("{} ", status.marker()))
+                            }
+                            Some(status) => Span::styled(
+                                format!("{} ", status.marker()),
+                                Style::default().fg(utils::git_color(status)),
+                            ),
+                            None => Span::raw("  "),
+                        };
+                        Line::from(vec![marker, Span::raw(text)])
+                    }
+                    _ => Line::from(text),
                 };
-                Ok(ListItem::new(text).style(style))
+                Ok(ListItem::new(line).style(style))
             })
             .collect::<Result<Vec<ListItem>, eyre::Error>>()
             .wrap_err(UI_ERROR_WRAP)
             .unwrap();
 
         if items.is_empty() {
-            items.push(ListItem::new("No Files").style(Style::default().fg(Color::Gray)));
+            let text = if state.opening_archive() {
+                "Loading..."
+            } else {
+                "No Files"
+            };
+            items.push(ListItem::new(text).style(Style::default().fg(Color::Gray)));
         }
 
         let list = List::new(items);
-        self.scroll_state.select(Some(state.selected));
-        f.render_stateful_widget(list, chunk, &mut self.scroll_state);
+        let scroll_state = &mut self.scroll_states[pane];
+        scroll_state.select(Some(state.selected));
+        f.render_stateful_widget(list, chunk, scroll_state);
+        self.areas.files.push(ListArea {
+            rect: chunk,
+            tab,
+            offset: scroll_state.offset(),
+        });
         Ok(())
     }
 
-    fn get_file_color(&self, kind: &std::fs::FileType) -> Result<Color> {
-        if kind.is_dir() {
-            return Ok(Color::Cyan);
-        }
-        if kind.is_file() {
-            return Ok(Color::White);
-        }
-        if kind.is_symlink() {
-            return Ok(Color::Green);
-        }
-        Err(eyre!("unreachable"))
+    fn draw_parent(
+        &mut self,
+        f: &mut Frame<'_, impl Backend>,
+        chunk: Rect,
+        tab: usize,
+        state: &State,
+    ) {
+        // leaves room for the path bar of the middle column
+        let chunk = Rect {
+            y: chunk.y + 1,
+            height: chunk.height.saturating_sub(1),
+            ..chunk
+        };
+        let selected = state.parent_files.iter().position(|f| f.path == state.path);
+        let items = state
+            .parent_files
+            .iter()
+            .enumerate()
+            .map(|(pos, file)| {
+                let color = utils::file_color(file.kind);
+                let style = if Some(pos) == selected {
+                    Style::default().fg(Color::Black).bg(color)
+                } else {
+                    Style::default().fg(color)
+                };
+                ListItem::new(file.name.to_string_lossy().to_string()).style(style)
+            })
+            .collect::<Vec<ListItem>>();
+
+        let scroll_state = &mut self.parent_scroll;
+        scroll_state.select(selected);
+        f.render_stateful_widget(List::new(items), chunk, scroll_state);
+        self.areas.parent = Some(ListArea {
+            rect: chunk,
+            tab,
+            offset: scroll_state.offset(),
+        });
     }
 
-    fn draw_input(&self, f: &mut Frame<'_, impl Backend>, state: &State) {
-        if state.mode != Mode::Basic {
+    fn draw_input(&mut self, f: &mut Frame<'_, impl Backend>, state: &State, ctx: &DrawContext) {
+        // choosing an ancestor happens in the path bar
+        if !matches!(state.mode, Mode::Basic | Mode::Ancestor(_)) {
             let title = match &state.mode {
-                Mode::Basic => "",
+                Mode::Basic | Mode::Ancestor(_) => "",
                 Mode::CreateFile(_) => "Create File",
                 Mode::RenameFile(_, _) => "Renaming file",
                 Mode::DeleteFile(_, _) => "Deleting file",
+                Mode::CopyFile(_, _) => "Copy to",
+                Mode::MoveFile(_, _) => "Move to",
+                Mode::History(_) => "History",
+                Mode::SetMark => "Set mark",
+                Mode::GotoMark => "Jump to mark",
+                Mode::AddBookmark(_) => "Bookmark as",
+                Mode::Bookmarks(_) => "Bookmarks (d: delete, r: rename)",
+                Mode::RenameBookmark(_, _) => "Rename bookmark",
+                Mode::Jump(_, _) => "Jump to",
+                Mode::GoTo(_) => "Go to (tab: complete)",
+                Mode::HexOffset(_) => "Go to offset (hex)",
+                Mode::Encoding(_) => "Encoding of this file (empty: detect)",
+                Mode::Compress(_, _) => "Archive name (.tar.gz, .tar.zst or .zip)",
+                Mode::ExtractTo(_, _) => "Extract to folder",
+                Mode::DiscardChanges(_, _) => "Discard unstaged changes (y: confirm)",
             };
 
+            if let Some(selected) = state.mode.get_list_selected() {
+                let items = match &state.mode {
+                    Mode::History(_) => self.history_items(state),
+                    Mode::Bookmarks(_) => self.bookmark_items(ctx.bookmarks),
+                    Mode::Jump(query, _) => ctx
+                        .frecency
+                        .query(query, JUMP_MATCHES)
+                        .into_iter()
+                        .map(|p| ListItem::new(shell::display_path(p)))
+                        .collect(),
+                    _ => vec![],
+                };
+                self.draw_list_popup(f, title, state.mode.get_str(), items, Some(selected));
+                return;
+            }
+            if let Mode::GoTo(input) = &state.mode {
+                let key = (state.path.clone(), input.clone());
+                if self.completions.as_ref().is_none_or(|(k, _)| k != &key) {
+                    let candidates = path::complete_candidates(input, &state.path);
+                    self.completions = Some((key, candidates));
+                }
+                let items = self
+                    .completions
+                    .iter()
+                    .flat_map(|(_, candidates)| candidates)
+                    .map(|n| ListItem::new(n.clone()).style(Style::default().fg(Color::Cyan)))
+                    .collect();
+                self.draw_list_popup(f, title, Some(input), items, None);
+                return;
+            }
+
             let block = Block::default().title(title).borders(Borders::ALL);
             let p = Paragraph::new(state.mode.get_str().unwrap_or("")).block(block);
             let area = utils::centered_rect(60, 3, f.size());
             f.render_widget(Clear, area); //this clears out the background
             f.render_widget(p, area);
+            self.areas.popup = Some(area);
         }
     }
 
-    fn draw_info(&mut self, f: &mut Frame<'_, impl Backend>, chunk: Rect, state: &State) {
+    fn draw_list_popup(
+        &mut self,
+        f: &mut Frame<'_, impl Backend>,
+        title: &str,
+        input: Option<&str>,
+        mut items: Vec<ListItem>,
+        selected: Option<usize>,
+    ) {
+        if items.is_empty() {
+            items.push(ListItem::new("Empty").style(Style::default().fg(Color::Gray)));
+        }
+        let max_height = f.size().height.saturating_sub(2);
+        // the border and a row do not fit
+        if max_height < 3 {
+            return;
+        }
+        let input_height = input.is_some() as u16;
+        let height = (items.len() as u16 + 2 + input_height).clamp(3, max_height);
+        let area = utils::centered_rect(60, height, f.size());
+
+        let block = Block::default().title(title).borders(Borders::ALL);
+        let inner = block.inner(area);
+        f.render_widget(Clear, area);
+        f.render_widget(block, area);
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(input_height), Constraint::Min(0)].as_ref())
+            .split(inner);
+        if let Some(input) = input {
+            f.render_widget(
+                Paragraph::new(input).style(Style::default().fg(Color::Yellow)),
+                layout[0],
+            );
+        }
+
+        let list =
+            List::new(items).highlight_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+        let mut list_state = ListState::default();
+        list_state.select(selected);
+        f.render_stateful_widget(list, layout[1], &mut list_state);
+        self.areas.popup = Some(area);
+        if selected.is_some() {
+            self.areas.popup_list = Some(ListArea {
+                rect: layout[1],
+                tab: 0,
+                offset: list_state.offset(),
+            });
+        }
+    }
+
+    fn bookmark_items(&self, bookmarks: &Bookmarks) -> Vec<ListItem<'static>> {
+        let width = bookmarks
+            .iter()
+            .map(|(n, _)| n.len())
+            .max()
+            .unwrap_or_default();
+        bookmarks
+            .iter()
+            .map(|(name, path)| {
+                // stale bookmarks are grayed out
+                let style = if path.is_dir() {
+                    Style::default()
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{name:<width$} "), Style::default().fg(Color::Cyan)),
+                    Span::styled(shell::display_path(path), style),
+                ]))
+            })
+            .collect()
+    }
+
+    fn history_items(&self, state: &State) -> Vec<ListItem<'static>> {
+        let today = Local::now().date_naive();
+        state
+            .history
+            .recent
+            .iter()
+            .map(|(path, time)| {
+                let time = if time.date_naive() == today {
+                    time.format("%H:%M:%S")
+                } else {
+                    time.format("%Y-%m-%d")
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{time:>10} "), Style::default().fg(Color::Gray)),
+                    Span::raw(shell::display_path(path)),
+                ]))
+            })
+            .collect()
+    }
+
+    fn draw_info(
+        &mut self,
+        f: &mut Frame<'_, impl Backend>,
+        chunk: Rect,
+        state: &State,
+        jobs: &[Job],
+    ) {
         if let Some(i) = state.info.last() {
             let p = match &i.kind {
                 InfoKind::Error(r) => Paragraph::new(
@@ -188,73 +635,84 @@ impl UiState {
                 InfoKind::Message(s) => Paragraph::new(s.to_string()),
             };
             f.render_widget(p, chunk)
-        } else {
-            // do something if there is nothing here
+        } else if let Some(job) = jobs.first() {
+            let mut text = job.name.clone();
+            if let Some(percent) = job.progress.percent() {
+                text.push_str(&format!(" {percent}%"));
+            }
+            if jobs.len() > 1 {
+                text.push_str(&format!(" (+{} more)", jobs.len() - 1));
+            }
+            f.render_widget(
+                Paragraph::new(text).style(Style::default().fg(Color::Gray)),
+                chunk,
+            );
         }
     }
     fn draw_content(&mut self, f: &mut Frame<'_, impl Backend>, chunk: Rect, state: &State) {
-        let border = Block::default().borders(Borders::LEFT);
-        let text = match state.files.get(state.selected) {
-            Some(file) if file.is_dir().unwrap() => {
-                let files = fs::read_dir(&file.path).unwrap().enumerate();
+        self.areas.preview = Some(chunk);
+        self.preview
+            .select(state.files.get(state.selected).map(|f| f.path.clone()));
 
-                let mut lines: Vec<Line> = vec![];
-                for (i, file) in files {
-                    if i > chunk.height as usize + 1_usize {
-                        break;
+        let border = match state.files.get(state.selected) {
+            Some(file) if self.preview.fullscreen => Block::default()
+                .title(shell::display_path(&file.path))
+                .borders(Borders::TOP),
+            _ => Block::default().borders(Borders::LEFT),
+        };
+        let text = match state.files.get(state.selected) {
+            // nothing inside an archive exists on disk
+            Some(file) if state.in_archive() => {
+                let archive = state.archive.as_ref().expect("checked by in_archive");
+                let inner = file.path.strip_prefix(&archive.path).unwrap_or(&file.path);
+                match file.kind {
+                    FileKind::Dir => {
+                        let mut lines = archive
+                            .children(inner)
+                            .take(chunk.height as usize)
+                            .map(|m| {
+                                Line::styled(
+                                    m.path.file_name().unwrap_or_default().to_string_lossy(),
+                                    Style::default().fg(utils::file_color(m.kind)),
+                                )
+                            })
+                            .collect::<Vec<_>>();
+                        if lines.is_empty() {
+                            lines.push(Line::styled("Empty", Style::default().fg(Color::Gray)));
+                        }
+                        lines
                     }
-                    let file = file.unwrap();
-                    lines.push(Line::from(Span::styled(
-                        file.file_name().to_string_lossy().to_string(),
-                        Style::default()
-                            .fg(self.get_file_color(&file.file_type().unwrap()).unwrap()),
-                    )))
-                }
-                if lines.is_empty() {
-                    lines.push(Line::from(Span::styled(
-                        "Empty",
+                    _ => vec![Line::styled(
+                        format!("{}, inside an archive", format_size(file.size)),
                         Style::default().fg(Color::Gray),
-                    )))
+                    )],
                 }
-                lines
             }
-            Some(file) if file.is_file().unwrap() => {
-                let ps = SyntaxSet::load_defaults_newlines();
-                let ts = ThemeSet::load_defaults();
-                let syntax = ps
-                    .find_syntax_by_extension(
-                        file.path.extension().unwrap_or_default().to_str().unwrap(),
-                    )
-                    .unwrap_or(ps.find_syntax_plain_text());
-
-                let mut h = HighlightLines::new(syntax, &ts.themes["Solarized (dark)"]);
-                let content = String::from_utf8(fs::read(&file.path).unwrap())
-                    .unwrap_or("Binary".to_string());
-                let mut lines: Vec<Line> = vec![];
-
-                for line in LinesWithEndings::from(&content) {
-                    let ranges: Vec<(syntect::highlighting::Style, &str)> =
-                        h.highlight_line(line, &ps).unwrap();
-                    let mut line = vec![];
-                    for (style, s) in ranges {
-                        line.push(Span::styled(s.to_string(), convert_sytax_style(style)));
-                    }
-                    lines.push(Line::from(line));
-                }
-                lines
+            Some(file) if file.is_dir().unwrap_or_default() => {
+                let inner = border.inner(chunk);
+                f.render_widget(border, chunk);
+                self.preview.draw_dir(f, inner, file, state.list);
+                return;
             }
-            Some(file) if file.file_type.is_symlink() => {
-                let path = file
-                    .path
-                    .canonicalize()
-                    .unwrap()
-                    .to_string_lossy()
-                    .to_string();
-                vec![Line::from(Span::styled(
-                    path,
-                    Style::default().fg(Color::LightBlue),
-                ))]
+            Some(file) if file.is_file().unwrap_or_default() => {
+                let inner = border.inner(chunk);
+                f.render_widget(border, chunk);
+                self.preview.draw_file(f, inner, file);
+                return;
             }
+            Some(file) if file.kind == FileKind::Symlink => match file.path.canonicalize() {
+                Ok(path) => vec![Line::styled(
+                    path.to_string_lossy().to_string(),
+                    Style::default().fg(Color::LightBlue),
+                )],
+                Err(_) => {
+                    let target = fs::read_link(&file.path).unwrap_or_default();
+                    vec![Line::styled(
+                        format!("{} (broken link)", target.to_string_lossy()),
+                        Style::default().fg(Color::Red),
+                    )]
+                }
+            },
             Some(_) => vec![Line::from(Span::styled(
                 "Unknown",
                 Style::default().fg(Color::Gray),
@@ -264,7 +722,8 @@ impl UiState {
                 Style::default().fg(Color::Gray),
             ))],
         };
-        let p = Paragraph::new(text).block(border);
-        f.render_widget(p, chunk)
+        let inner = border.inner(chunk);
+        f.render_widget(border, chunk);
+        self.preview.draw(f, inner, &text, None);
     }
 }
@@ -0,0 +1,225 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::prelude::Rect;
+
+use super::input::{InputModeResult, InputResult, PreviewAction, ScrollAmount, TabAction};
+use crate::{Mode, State};
+
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+// how many entries one step of the scroll wheel moves
+pub const SCROLL_STEP: usize = 3;
+
+/// A list of files on screen, `offset` is how far the list is scrolled
+pub struct ListArea {
+    pub rect: Rect,
+    pub tab: usize,
+    pub offset: usize,
+}
+
+/// Where things were drawn last frame, so clicks can be matched to them
+#[derive(Default)]
+pub struct Areas {
+    pub tabs: Vec<(Rect, usize)>,
+    pub path: Vec<(Rect, usize, PathBuf)>,
+    pub files: Vec<ListArea>,
+    pub parent: Option<ListArea>,
+    pub preview: Option<Rect>,
+    pub popup: Option<Rect>,
+    // the list inside of the popup, if it has one
+    pub popup_list: Option<ListArea>,
+}
+
+fn contains(rect: &Rect, column: u16, row: u16) -> bool {
+    column >= rect.x
+        && column < rect.x.saturating_add(rect.width)
+        && row >= rect.y
+        && row < rect.y.saturating_add(rect.height)
+}
+
+fn list_index(area: &ListArea, row: u16) -> usize {
+    area.offset + (row - area.rect.y) as usize
+}
+
+pub fn match_mouse(
+    event: MouseEvent,
+    areas: &Areas,
+    state: &State,
+    last_click: &mut Option<(Instant, u16, u16)>,
+) -> InputResult {
+    let (column, row) = (event.column, event.row);
+
+    match event.kind {
+        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+            let up = event.kind == MouseEventKind::ScrollUp;
+            if state.mode.get_list_selected().is_some() {
+                return InputResult::Mode(if up {
+                    InputModeResult::MoveUp
+                } else {
+                    InputModeResult::MoveDown
+                });
+            }
+            // nothing under an open popup scrolls
+            if areas.popup.is_some() {
+                return InputResult::Skip;
+            }
+            if areas.preview.is_some_and(|r| contains(&r, column, row)) {
+                return InputResult::Preview(PreviewAction::Scroll {
+                    amount: ScrollAmount::Lines(SCROLL_STEP),
+                    up,
+                });
+            }
+            match areas.files.iter().find(|a| contains(&a.rect, column, row)) {
+                Some(area) => InputResult::Scroll { tab: area.tab, up },
+                None => InputResult::Skip,
+            }
+        }
+        MouseEventKind::Down(MouseButton::Left) => {
+            let double = last_click.is_some_and(|(time, c, r)| {
+                time.elapsed() < DOUBLE_CLICK && c == column && r == row
+            });
+            *last_click = Some((Instant::now(), column, row));
+
+            // popups take all the clicks while they are open
+            if let Some(popup) = areas.popup {
+                if !contains(&popup, column, row) {
+                    return InputResult::Mode(InputModeResult::ModeChange(Mode::Basic));
+                }
+                return match &areas.popup_list {
+                    Some(list) if contains(&list.rect, column, row) => {
+                        InputResult::Mode(InputModeResult::Select(list_index(list, row)))
+                    }
+                    _ => InputResult::Skip,
+                };
+            }
+
+            if let Some((_, tab)) = areas.tabs.iter().find(|(r, _)| contains(r, column, row)) {
+                return InputResult::Tab(TabAction::Select(*tab));
+            }
+            if let Some((_, tab, path)) = areas.path.iter().find(|(r, ..)| contains(r, column, row))
+            {
+                return InputResult::GoTo {
+                    tab: *tab,
+                    path: path.clone(),
+                };
+            }
+            if let Some(area) = areas.files.iter().find(|a| contains(&a.rect, column, row)) {
+                return InputResult::Click {
+                    tab: area.tab,
+                    index: list_index(area, row),
+                    double,
+                };
+            }
+            if let Some(area) = areas
+                .parent
+                .as_ref()
+                .filter(|a| contains(&a.rect, column, row))
+            {
+                return match state.parent_files.get(list_index(area, row)) {
+                    Some(file) if file.is_dir().unwrap_or_default() => InputResult::GoTo {
+                        tab: area.tab,
+                        path: file.path.clone(),
+                    },
+                    _ => InputResult::Skip,
+                };
+            }
+            InputResult::Skip
+        }
+        _ => InputResult::Skip,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crossterm::event::KeyModifiers;
+
+    use super::*;
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn match_mouse_tests() {
+        let mut areas = Areas {
+            tabs: vec![(Rect::new(0, 0, 10, 1), 1)],
+            files: vec![ListArea {
+                rect: Rect::new(0, 2, 20, 10),
+                tab: 0,
+                offset: 5,
+            }],
+            preview: Some(Rect::new(20, 2, 20, 10)),
+            ..Default::default()
+        };
+        let mut state = State::default();
+        let mut last_click = None;
+        let mut input = |kind, column, row, areas: &Areas, state: &State| {
+            match_mouse(mouse(kind, column, row), areas, state, &mut last_click)
+        };
+        let left = MouseEventKind::Down(MouseButton::Left);
+
+        assert!(matches!(
+            input(MouseEventKind::ScrollDown, 25, 5, &areas, &state),
+            InputResult::Preview(PreviewAction::Scroll { up: false, .. })
+        ));
+        assert!(matches!(
+            input(MouseEventKind::ScrollUp, 5, 5, &areas, &state),
+            InputResult::Scroll { tab: 0, up: true }
+        ));
+        assert!(matches!(
+            input(left, 5, 0, &areas, &state),
+            InputResult::Tab(TabAction::Select(1))
+        ));
+        assert!(matches!(
+            input(left, 5, 4, &areas, &state),
+            InputResult::Click {
+                tab: 0,
+                index: 7,
+                double: false
+            }
+        ));
+        assert!(matches!(
+            input(left, 5, 4, &areas, &state),
+            InputResult::Click { double: true, .. }
+        ));
+
+        // a popup over the preview takes the wheel and the clicks
+        state.mode = Mode::GoTo(String::new());
+        areas.popup = Some(Rect::new(18, 3, 10, 5));
+        areas.popup_list = Some(ListArea {
+            rect: Rect::new(19, 4, 8, 3),
+            tab: 0,
+            offset: 2,
+        });
+        assert!(matches!(
+            input(MouseEventKind::ScrollDown, 25, 5, &areas, &state),
+            InputResult::Skip
+        ));
+        assert!(matches!(
+            input(left, 20, 5, &areas, &state),
+            InputResult::Mode(InputModeResult::Select(3))
+        ));
+        assert!(matches!(
+            input(left, 20, 3, &areas, &state),
+            InputResult::Skip
+        ));
+        assert!(matches!(
+            input(left, 5, 5, &areas, &state),
+            InputResult::Mode(InputModeResult::ModeChange(Mode::Basic))
+        ));
+
+        state.mode = Mode::History(0);
+        assert!(matches!(
+            input(MouseEventKind::ScrollDown, 25, 5, &areas, &state),
+            InputResult::Mode(InputModeResult::MoveDown)
+        ));
+    }
+}
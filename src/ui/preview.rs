@@ -0,0 +1,925 @@
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver},
+        Arc, OnceLock,
+    },
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Local};
+use encoding_rs::Encoding;
+use eyre::Result;
+use image::{ImageReader, RgbaImage};
+use ratatui::{
+    prelude::{Alignment, Backend, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Wrap},
+    Frame,
+};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::ThemeSet,
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+use super::{
+    ansi,
+    graphics::{fit, is_image, kitty, sixel, Graphic, HalfBlocks, ImageProtocol},
+    input::{PreviewAction, ScrollAmount},
+    markdown,
+    utils::{convert_sytax_style, file_color},
+};
+use crate::{
+    filesystem::{
+        archive::{members, ArchiveKind},
+        preview::{
+            ascii_row, hex_row, read_binary_at, read_head, read_tail, Data, PreviewOptions,
+            HEX_WIDTH,
+        },
+        previewer::{Previewer, Previewers},
+        read::{dir_size, read_path, File, FileKind},
+        sort::ListOptions,
+        utils::{format_mode, format_size},
+    },
+    git::{self, BlameLine},
+};
+
+// loading them takes a while, so it is only done once
+fn syntax_set() -> &'static SyntaxSet {
+    static SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme_set() -> &'static ThemeSet {
+    static SET: OnceLock<ThemeSet> = OnceLock::new();
+    SET.get_or_init(ThemeSet::load_defaults)
+}
+
+/// What a preview was read from, it is read again once any of it changes
+#[derive(Clone, PartialEq)]
+struct Source {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    size: u64,
+    tail: bool,
+    // shown as a hex dump from there
+    offset: Option<u64>,
+    encoding: Option<&'static Encoding>,
+    // rendered instead of highlighted
+    markdown: bool,
+    git: Option<GitView>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GitView {
+    Diff,
+    Blame,
+}
+
+struct Loaded {
+    source: Source,
+    lines: Vec<Line<'static>>,
+    hex: bool,
+    // the encoding and line endings
+    header: Option<String>,
+    image: Option<Arc<RgbaImage>>,
+}
+
+// longer names are cut in the blame
+const MAX_AUTHOR_WIDTH: usize = 16;
+// larger images are scaled down when read, they only get a few cells anyway
+const MAX_IMAGE_SIZE: u32 = 2048;
+
+fn load(source: &Source, opts: PreviewOptions, previewers: &Previewers) -> Loaded {
+    if let Some(view) = source.git {
+        return load_git(source, view, opts);
+    }
+    if let Some((previewer, output)) =
+        previewers.preview(&source.path, source.modified, opts.max_bytes)
+    {
+        return load_external(source, previewer, output, opts);
+    }
+    if let Some(kind) = ArchiveKind::detect(&source.path) {
+        return load_archive(source, kind, opts);
+    }
+    if is_image(&source.path) {
+        return load_image(source);
+    }
+    let dim = Style::default().fg(Color::DarkGray);
+    let content = match source.offset {
+        Some(offset) => read_binary_at(&source.path, opts, offset),
+        None if source.tail => read_tail(&source.path, opts, source.encoding),
+        None => read_head(&source.path, opts, source.encoding),
+    };
+    let content = match content {
+        Ok(content) => content,
+        Err(e) => {
+            let error = Line::styled(e.to_string(), Style::default().fg(Color::Red));
+            return Loaded {
+                source: source.clone(),
+                lines: vec![error],
+                hex: false,
+                header: None,
+                image: None,
+            };
+        }
+    };
+
+    let hex = matches!(content.data, Data::Binary(_));
+    let mut lines = match &content.data {
+        Data::Text(text) if source.markdown => markdown::render(text),
+        Data::Text(text) => highlight(&source.path, text),
+        Data::Binary(bytes) => hex_dump(bytes, content.offset),
+    };
+
+    let size = format_size(content.size);
+    if content.cut_start {
+        let marker = match hex {
+            true => format!("--- starting at {:#x}, {size} in total ---", content.offset),
+            false => format!("--- earlier lines not shown, {size} in total ---"),
+        };
+        lines.insert(0, Line::styled(marker, dim));
+    }
+    if content.cut_end {
+        lines.push(Line::styled(
+            format!("--- truncated, {size} in total ---"),
+            dim,
+        ));
+    }
+
+    let header = match content.encoding {
+        Some(encoding) => {
+            let mut header = encoding.name().to_string();
+            if let Some(ending) = content.line_ending {
+                header.push_str(&format!(" · {}", ending.name()));
+            }
+            if source.encoding.is_some() {
+                header.push_str(" (forced)");
+            }
+            if source.markdown {
+                header.push_str(" · rendered");
+            }
+            header
+        }
+        None => "hex".to_string(),
+    };
+    Loaded {
+        source: source.clone(),
+        lines,
+        hex,
+        header: Some(header),
+        image: None,
+    }
+}
+
+fn diff_lines(diff: &str, opts: PreviewOptions) -> Vec<Line<'static>> {
+    if diff.is_empty() {
+        return vec![Line::styled("No changes", Style::default().fg(Color::Gray))];
+    }
+    diff.lines()
+        .take(opts.max_lines)
+        .map(|line| {
+            let style = Style::default();
+            let style = match line.as_bytes().first() {
+                _ if line.starts_with("+++") || line.starts_with("---") => {
+                    style.add_modifier(Modifier::BOLD)
+                }
+                Some(b'+') => style.fg(Color::Green),
+                Some(b'-') => style.fg(Color::Red),
+                _ if line.starts_with("@@") => style.fg(Color::Cyan),
+                Some(b' ') => style,
+                // the headers of the diff
+                _ => style.fg(Color::DarkGray),
+            };
+            Line::styled(line.to_string(), style)
+        })
+        .collect()
+}
+
+// the commit, author and date of each line before the highlighted line itself
+fn blame_lines(path: &Path, blame: &[BlameLine], opts: PreviewOptions) -> Vec<Line<'static>> {
+    let blame = &blame[..blame.len().min(opts.max_lines)];
+    let text = blame
+        .iter()
+        .map(|l| l.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let author_width = blame
+        .iter()
+        .map(|l| l.author.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(MAX_AUTHOR_WIDTH);
+    let dim = Style::default().fg(Color::DarkGray);
+    blame
+        .iter()
+        .zip(highlight(path, &text))
+        .map(|(blame, mut line)| {
+            let author = blame.author.chars().take(author_width).collect::<String>();
+            let date = DateTime::from_timestamp(blame.time, 0)
+                .map(|t| t.with_timezone(&Local).format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            let mut spans = vec![
+                Span::styled(format!("{} ", blame.hash), dim),
+                Span::styled(
+                    format!("{author:<author_width$} "),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::styled(format!("{date} │ "), dim),
+            ];
+            spans.append(&mut line.spans);
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Shows the diff or blame of a file
+fn load_git(source: &Source, view: GitView, opts: PreviewOptions) -> Loaded {
+    let lines = match view {
+        GitView::Diff => git::diff(&source.path).map(|d| diff_lines(&d, opts)),
+        GitView::Blame => git::blame(&source.path).map(|b| blame_lines(&source.path, &b, opts)),
+    };
+    let lines = lines.unwrap_or_else(|e| {
+        vec![Line::styled(
+            format!("{e:#}"),
+            Style::default().fg(Color::Red),
+        )]
+    });
+    Loaded {
+        source: source.clone(),
+        lines,
+        hex: false,
+        header: Some(match view {
+            GitView::Diff => "diff".to_string(),
+            GitView::Blame => "blame".to_string(),
+        }),
+        image: None,
+    }
+}
+
+/// Shows the output of a previewer command, with its colors
+fn load_external(
+    source: &Source,
+    previewer: &Previewer,
+    output: Result<String>,
+    opts: PreviewOptions,
+) -> Loaded {
+    let lines = match output {
+        Ok(output) => {
+            let mut lines = ansi::to_lines(&output);
+            if lines.len() > opts.max_lines || output.len() as u64 >= opts.max_bytes {
+                lines.truncate(opts.max_lines);
+                lines.push(Line::styled(
+                    "--- truncated ---",
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            lines
+        }
+        Err(e) => vec![Line::styled(
+            format!("{e:#}"),
+            Style::default().fg(Color::Red),
+        )],
+    };
+    Loaded {
+        source: source.clone(),
+        lines,
+        hex: false,
+        header: Some(previewer.program().to_string()),
+        image: None,
+    }
+}
+
+/// Lists the members of an archive like `ls -l` would
+fn load_archive(source: &Source, kind: ArchiveKind, opts: PreviewOptions) -> Loaded {
+    let dim = Style::default().fg(Color::DarkGray);
+    let members = match members(&source.path) {
+        Ok(members) => members,
+        Err(e) => {
+            return Loaded {
+                source: source.clone(),
+                lines: vec![Line::styled(e.to_string(), Style::default().fg(Color::Red))],
+                hex: false,
+                header: Some(kind.name().to_string()),
+                image: None,
+            }
+        }
+    };
+
+    let mut lines = members
+        .iter()
+        .take(opts.max_lines)
+        .map(|m| {
+            let modified = m
+                .modified
+                .map(|t| {
+                    DateTime::<Local>::from(t)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_else(|| "-".repeat(16));
+            let mut name = m.path.to_string_lossy().to_string();
+            if m.kind == FileKind::Dir {
+                name.push('/');
+            }
+            let mut line = Line::from(vec![
+                Span::styled(format!("{:<10}", format_mode(m.kind, m.mode, false)), dim),
+                Span::raw(format!(" {:>7} ", format_size(m.size))),
+                Span::styled(modified, dim),
+                Span::styled(format!(" {name}"), Style::default().fg(file_color(m.kind))),
+            ]);
+            if let Some(link) = &m.link {
+                line.spans
+                    .push(Span::styled(format!(" -> {}", link.display()), dim));
+            }
+            line
+        })
+        .collect::<Vec<_>>();
+    if members.len() > opts.max_lines {
+        lines.push(Line::styled(
+            format!("--- truncated, {} entries in total ---", members.len()),
+            dim,
+        ));
+    }
+
+    let total = members.iter().map(|m| m.size).sum::<u64>();
+    Loaded {
+        source: source.clone(),
+        lines,
+        hex: false,
+        header: Some(format!(
+            "{} · {} entries, {} unpacked",
+            kind.name(),
+            members.len(),
+            format_size(total)
+        )),
+        image: None,
+    }
+}
+
+/// Decodes an image, the header has its format and size
+fn load_image(source: &Source) -> Loaded {
+    let decode = || -> Result<(String, RgbaImage)> {
+        let reader = ImageReader::open(&source.path)?.with_guessed_format()?;
+        let format = reader
+            .format()
+            .and_then(|f| f.extensions_str().first())
+            .map(|e| e.to_uppercase())
+            .unwrap_or_default();
+        let image = reader.decode()?;
+        let header = format!("{format} · {}x{}", image.width(), image.height());
+        let image = if image.width().max(image.height()) > MAX_IMAGE_SIZE {
+            image.thumbnail(MAX_IMAGE_SIZE, MAX_IMAGE_SIZE)
+        } else {
+            image
+        };
+        Ok((header, image.into_rgba8()))
+    };
+    let (lines, header, image) = match decode() {
+        Ok((header, image)) => (vec![], Some(header), Some(Arc::new(image))),
+        Err(e) => (
+            vec![Line::styled(e.to_string(), Style::default().fg(Color::Red))],
+            None,
+            None,
+        ),
+    };
+    Loaded {
+        source: source.clone(),
+        lines,
+        hex: false,
+        header,
+        image,
+    }
+}
+
+fn highlight(path: &Path, text: &str) -> Vec<Line<'static>> {
+    let ps = syntax_set();
+    let syntax = path
+        .extension()
+        .and_then(|e| ps.find_syntax_by_extension(&e.to_string_lossy()))
+        .unwrap_or(ps.find_syntax_plain_text());
+    highlight_with(syntax, text)
+}
+
+/// Highlights code by the name of its language, like in Markdown code blocks
+pub fn highlight_code(lang: &str, text: &str) -> Vec<Line<'static>> {
+    let ps = syntax_set();
+    let syntax = ps
+        .find_syntax_by_token(lang)
+        .unwrap_or(ps.find_syntax_plain_text());
+    highlight_with(syntax, text)
+}
+
+fn highlight_with(syntax: &SyntaxReference, text: &str) -> Vec<Line<'static>> {
+    let ps = syntax_set();
+    let mut h = HighlightLines::new(syntax, &theme_set().themes["Solarized (dark)"]);
+
+    let mut lines = vec![];
+    for line in LinesWithEndings::from(text) {
+        let spans = match h.highlight_line(line, ps) {
+            Ok(ranges) => ranges
+                .into_iter()
+                .map(|(style, s)| Span::styled(s.to_string(), convert_sytax_style(style)))
+                .collect(),
+            Err(_) => vec![Span::raw(line.to_string())],
+        };
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+fn hex_dump(bytes: &[u8], offset: u64) -> Vec<Line<'static>> {
+    bytes
+        .chunks(HEX_WIDTH)
+        .enumerate()
+        .map(|(i, row)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:08x}  ", offset + (i * HEX_WIDTH) as u64),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::raw(hex_row(row)),
+                Span::styled(
+                    format!(" {}", ascii_row(row)),
+                    Style::default().fg(Color::Cyan),
+                ),
+            ])
+        })
+        .collect()
+}
+
+/// A directory being previewed. Its entries are read in the background, and its size is added
+/// up after that
+struct DirListing {
+    key: (PathBuf, Option<SystemTime>, ListOptions),
+    receiver: Receiver<Result<Vec<File>>>,
+    // the counts by type and the entries, once read
+    loaded: Option<(String, Vec<Line<'static>>)>,
+    size: Arc<AtomicU64>,
+    size_done: Arc<AtomicBool>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl DirListing {
+    fn read(key: (PathBuf, Option<SystemTime>, ListOptions)) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let (path, list) = (key.0.clone(), key.2);
+        tokio::spawn(async move {
+            let mut files = read_path(&path).await;
+            if let Ok(files) = &mut files {
+                list.apply(files);
+            }
+            let _ = sender.send(files);
+        });
+
+        let size = Arc::new(AtomicU64::new(0));
+        let size_done = Arc::new(AtomicBool::new(false));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (path, task_size, task_done, task_cancel) = (
+            key.0.clone(),
+            size.clone(),
+            size_done.clone(),
+            cancel.clone(),
+        );
+        tokio::task::spawn_blocking(move || {
+            dir_size(&path, &task_size, &task_cancel);
+            task_done.store(true, Ordering::Relaxed);
+        });
+
+        Self {
+            key,
+            receiver,
+            loaded: None,
+            size,
+            size_done,
+            cancel,
+        }
+    }
+
+    fn summary(files: &[File]) -> String {
+        let kinds = [
+            (FileKind::Dir, "dir", "dirs"),
+            (FileKind::File, "file", "files"),
+            (FileKind::Symlink, "link", "links"),
+            (FileKind::Other, "other", "other"),
+        ];
+        let counts = kinds
+            .into_iter()
+            .filter_map(|(kind, one, many)| {
+                let n = files.iter().filter(|f| f.kind == kind).count();
+                (n > 0).then(|| format!("{n} {}", if n == 1 { one } else { many }))
+            })
+            .collect::<Vec<_>>();
+        counts.join(" · ")
+    }
+
+    fn lines(files: &[File]) -> Vec<Line<'static>> {
+        if files.is_empty() {
+            return vec![Line::styled("Empty", Style::default().fg(Color::Gray))];
+        }
+        files
+            .iter()
+            .map(|f| {
+                Line::styled(
+                    f.name.to_string_lossy().to_string(),
+                    Style::default().fg(file_color(f.kind)),
+                )
+            })
+            .collect()
+    }
+}
+
+impl Drop for DirListing {
+    // the size is not needed anymore
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// How the preview is shown and how far it is scrolled
+#[derive(Default)]
+pub struct Preview {
+    pub scroll: usize,
+    pub line_numbers: bool,
+    pub wrap: bool,
+    pub fullscreen: bool,
+    // Markdown files are rendered instead of showing their source
+    pub markdown: bool,
+    // files in a repository show their diff or blame instead
+    pub git: Option<GitView>,
+    // shows the end of files and follows them as they grow
+    pub tail: bool,
+    pub options: PreviewOptions,
+    // commands for the files that are not previewed by onyx itself
+    pub previewers: Arc<Previewers>,
+    // where the hex dump was jumped to
+    offset: Option<u64>,
+    // overrides the detected encoding, only for the file it was picked for
+    pub encoding: Option<&'static Encoding>,
+    // the file the scroll belongs to
+    path: Option<PathBuf>,
+    loaded: Option<Loaded>,
+    pending: Option<(Source, Receiver<Loaded>)>,
+    // from the last frame, pages are scrolled by its height
+    height: usize,
+    len: usize,
+    pub protocol: ImageProtocol,
+    // a popup is open, escape code images would be drawn over it
+    pub covered: bool,
+    // the image to draw over this frame, and the one that is on screen
+    pub graphic: Option<Graphic>,
+    shown: Option<Graphic>,
+    image_cache: Option<(Arc<RgbaImage>, Graphic)>,
+    dir: Option<DirListing>,
+}
+
+impl Preview {
+    pub fn handle(&mut self, action: PreviewAction) {
+        match action {
+            PreviewAction::Scroll { amount, up } => {
+                let amount = match amount {
+                    ScrollAmount::Lines(n) => n,
+                    ScrollAmount::HalfPage => (self.height / 2).max(1),
+                    ScrollAmount::Page => self.height.max(1),
+                };
+                self.scroll = if up {
+                    self.scroll.saturating_sub(amount)
+                } else {
+                    self.scroll.saturating_add(amount).min(self.max_scroll())
+                };
+            }
+            PreviewAction::ToggleLineNumbers => self.line_numbers = !self.line_numbers,
+            PreviewAction::ToggleWrap => self.wrap = !self.wrap,
+            PreviewAction::ToggleFullscreen => self.fullscreen = !self.fullscreen,
+            PreviewAction::ToggleTail => self.tail = !self.tail,
+            PreviewAction::ToggleMarkdown => self.markdown = !self.markdown,
+            PreviewAction::ToggleDiff => self.toggle_git(GitView::Diff),
+            PreviewAction::ToggleBlame => self.toggle_git(GitView::Blame),
+        }
+    }
+
+    fn toggle_git(&mut self, view: GitView) {
+        self.git = (self.git != Some(view)).then_some(view);
+        self.scroll = 0;
+    }
+
+    /// Starts at the top again when another file is previewed, and detects its encoding again
+    pub fn select(&mut self, path: Option<PathBuf>) {
+        if path != self.path {
+            self.scroll = 0;
+            self.offset = None;
+            self.encoding = None;
+            self.path = path;
+        }
+    }
+
+    /// If the current preview is a hex dump
+    pub fn is_hex(&self) -> bool {
+        self.loaded.as_ref().is_some_and(|l| l.hex)
+    }
+
+    pub fn jump_to(&mut self, offset: u64) {
+        self.offset = Some(offset);
+        self.scroll = 0;
+    }
+
+    // wrapped lines can take more than one row, so the last line has to be reachable
+    fn max_scroll(&self) -> usize {
+        if self.wrap {
+            self.len.saturating_sub(1)
+        } else {
+            self.len.saturating_sub(self.height)
+        }
+    }
+
+    /// Draws a file, it is read in the background so large files do not block the ui
+    pub fn draw_file(&mut self, f: &mut Frame<'_, impl Backend>, chunk: Rect, file: &File) {
+        let path = file.path.as_path();
+        let source = Source {
+            path: path.to_path_buf(),
+            modified: file.modified,
+            size: file.size,
+            tail: self.tail,
+            offset: self.offset,
+            encoding: self.encoding,
+            markdown: self.markdown && markdown::is_markdown(path),
+            git: self.git.filter(|_| git::root(path).is_some()),
+        };
+
+        if let Some((_, receiver)) = &self.pending {
+            if let Ok(loaded) = receiver.try_recv() {
+                if loaded.source.tail && loaded.source.offset.is_none() {
+                    // follow the end
+                    self.scroll = usize::MAX;
+                }
+                self.loaded = Some(loaded);
+                self.pending = None;
+            }
+        }
+        let is_loaded = self.loaded.as_ref().is_some_and(|l| l.source == source);
+        let is_pending = self.pending.as_ref().is_some_and(|(s, _)| s == &source);
+        if !is_loaded && !is_pending {
+            // an older read that is still running just has nobody to send to
+            let (sender, receiver) = mpsc::channel();
+            let opts = self.options;
+            let previewers = self.previewers.clone();
+            let task_source = source.clone();
+            tokio::task::spawn_blocking(move || {
+                let _ = sender.send(load(&task_source, opts, &previewers));
+            });
+            self.pending = Some((source, receiver));
+        }
+
+        // keep showing the old content while the same file is read again
+        match self.loaded.take() {
+            Some(loaded) if loaded.source.path == path && loaded.image.is_some() => {
+                let image = loaded.image.clone().expect("checked above");
+                self.draw_image(f, chunk, &image, loaded.header.as_deref());
+                self.loaded = Some(loaded);
+            }
+            Some(loaded) if loaded.source.path == path => {
+                self.draw(f, chunk, &loaded.lines, loaded.header.as_deref());
+                self.loaded = Some(loaded);
+            }
+            loaded => {
+                let loading = Line::styled("Loading...", Style::default().fg(Color::Gray));
+                self.draw(f, chunk, &[loading], None);
+                self.loaded = loaded;
+            }
+        }
+    }
+
+    /// Draws the entries of a directory like the main list would show them, with their counts and
+    /// total size
+    pub fn draw_dir(
+        &mut self,
+        f: &mut Frame<'_, impl Backend>,
+        chunk: Rect,
+        dir: &File,
+        list: ListOptions,
+    ) {
+        let key = (dir.path.clone(), dir.modified, list);
+        if self.dir.as_ref().is_none_or(|d| d.key != key) {
+            let mut listing = DirListing::read(key);
+            // keep showing the old entries while the same directory is read again
+            if let Some(old) = self.dir.take().filter(|d| d.key.0 == dir.path) {
+                listing.loaded = old.loaded.clone();
+            }
+            self.dir = Some(listing);
+        }
+        let Some(mut listing) = self.dir.take() else {
+            return;
+        };
+        if let Ok(files) = listing.receiver.try_recv() {
+            listing.loaded = Some(match files {
+                Ok(files) => (DirListing::summary(&files), DirListing::lines(&files)),
+                Err(e) => (
+                    String::new(),
+                    vec![Line::styled(
+                        format!("{e:#}"),
+                        Style::default().fg(Color::Red),
+                    )],
+                ),
+            });
+        }
+
+        match &listing.loaded {
+            Some((counts, lines)) => {
+                let mut size = format_size(listing.size.load(Ordering::Relaxed));
+                if !listing.size_done.load(Ordering::Relaxed) {
+                    size.push('…');
+                }
+                let header = match counts.is_empty() {
+                    true => size,
+                    false => format!("{counts} · {size}"),
+                };
+                self.draw(f, chunk, lines, Some(&header));
+            }
+            None => {
+                let loading = Line::styled("Loading...", Style::default().fg(Color::Gray));
+                self.draw(f, chunk, &[loading], None);
+            }
+        }
+        self.dir = Some(listing);
+    }
+
+    // the header goes in the first row, the rest is left for the content
+    fn draw_header(f: &mut Frame<'_, impl Backend>, chunk: Rect, header: Option<&str>) -> Rect {
+        match header {
+            Some(header) => {
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(1), Constraint::Min(0)])
+                    .split(chunk);
+                let dim = Style::default().fg(Color::DarkGray);
+                f.render_widget(Paragraph::new(header).style(dim), layout[0]);
+                layout[1]
+            }
+            None => chunk,
+        }
+    }
+
+    fn draw_image(
+        &mut self,
+        f: &mut Frame<'_, impl Backend>,
+        chunk: Rect,
+        image: &Arc<RgbaImage>,
+        header: Option<&str>,
+    ) {
+        let chunk = Self::draw_header(f, chunk, header);
+        self.len = 0;
+        match self.protocol {
+            ImageProtocol::Kitty | ImageProtocol::Sixel if !self.covered => {
+                let area = fit(image.width(), image.height(), chunk);
+                // encoding takes a while, so it is only done when the image or its place changed
+                let graphic = match self.image_cache.take() {
+                    Some((cached, graphic))
+                        if Arc::ptr_eq(&cached, image) && graphic.area == area =>
+                    {
+                        graphic
+                    }
+                    _ => Graphic {
+                        area,
+                        protocol: self.protocol,
+                        data: match self.protocol {
+                            ImageProtocol::Kitty => kitty(image, area),
+                            _ => sixel(image, area),
+                        }
+                        .into(),
+                    },
+                };
+                self.image_cache = Some((image.clone(), graphic.clone()));
+                self.graphic = Some(graphic);
+            }
+            _ => f.render_widget(HalfBlocks(image), chunk),
+        }
+    }
+
+    /// If the screen has to be cleared before drawing the next image, sixel images stay until
+    /// something is drawn over them
+    pub fn needs_clear(&self) -> bool {
+        self.graphic != self.shown
+            && self
+                .shown
+                .as_ref()
+                .is_some_and(|s| s.protocol == ImageProtocol::Sixel)
+    }
+
+    /// Writes the image of this frame once it changed
+    pub fn show_graphic(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if self.graphic != self.shown {
+            Graphic::show(self.graphic.as_ref(), self.shown.as_ref(), out)?;
+            self.shown = self.graphic.clone();
+        }
+        Ok(())
+    }
+
+    pub fn draw(
+        &mut self,
+        f: &mut Frame<'_, impl Backend>,
+        chunk: Rect,
+        lines: &[Line<'_>],
+        header: Option<&str>,
+    ) {
+        let chunk = Self::draw_header(f, chunk, header);
+
+        self.len = lines.len();
+        let overflows = self.len > chunk.height as usize;
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(overflows.into())])
+            .split(chunk);
+        self.height = layout[0].height as usize;
+        self.scroll = self.scroll.min(self.max_scroll());
+
+        let width = self.len.to_string().len();
+        let lines = lines
+            .iter()
+            .cloned()
+            .enumerate()
+            .skip(self.scroll)
+            .take(self.height)
+            .map(|(i, mut line)| {
+                if self.line_numbers {
+                    let number = format!("{:>width$} ", i + 1);
+                    line.spans.insert(
+                        0,
+                        Span::styled(number, Style::default().fg(Color::DarkGray)),
+                    );
+                }
+                line
+            })
+            .collect::<Vec<_>>();
+
+        let mut p = Paragraph::new(lines);
+        if self.wrap {
+            p = p.wrap(Wrap { trim: false });
+        }
+        f.render_widget(p, layout[0]);
+
+        if overflows {
+            let last = (self.scroll + self.height).min(self.len);
+            let position = format!(
+                "{}{}-{last}/{} {}%",
+                if self.tail { "tail " } else { "" },
+                self.scroll + 1,
+                self.len,
+                last * 100 / self.len
+            );
+            let position = Paragraph::new(position)
+                .style(Style::default().fg(Color::DarkGray))
+                .alignment(Alignment::Right);
+            f.render_widget(position, layout[1]);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn dir_listing_tests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        fs::write(dir.join("a.txt"), "hello").unwrap();
+        std::os::unix::fs::symlink(dir.join("a.txt"), dir.join("link")).unwrap();
+        fs::create_dir(dir.join("locked")).unwrap();
+        fs::write(dir.join("locked/b.txt"), "x".repeat(100)).unwrap();
+        fs::set_permissions(dir.join("locked"), fs::Permissions::from_mode(0o000)).unwrap();
+
+        let files = read_path(&dir).await.unwrap();
+        assert_eq!(DirListing::summary(&files), "1 dir · 1 file · 1 link");
+        let mut names = DirListing::lines(&files)
+            .into_iter()
+            .map(|l| l.spans[0].content.to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["a.txt", "link", "locked"]);
+        assert_eq!(DirListing::summary(&[]), "");
+        assert_eq!(DirListing::lines(&[])[0].spans[0].content, "Empty");
+
+        // the link itself is counted, not what it points to. Root can still read the locked dir
+        let link = fs::symlink_metadata(dir.join("link")).unwrap().len();
+        let locked = match fs::read_dir(dir.join("locked")) {
+            Ok(_) => 100,
+            Err(_) => 0,
+        };
+        let total = AtomicU64::new(0);
+        dir_size(&dir, &total, &AtomicBool::new(false));
+        assert_eq!(total.load(Ordering::Relaxed), 5 + link + locked);
+
+        let total = AtomicU64::new(0);
+        dir_size(&dir, &total, &AtomicBool::new(true));
+        assert_eq!(total.load(Ordering::Relaxed), 0);
+
+        fs::set_permissions(dir.join("locked"), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+}
@@ -1,5 +1,7 @@
 use ratatui::prelude::*;
 
+use crate::{filesystem::read::FileKind, git::FileStatus};
+
 pub fn centered_rect(percent_x: u16, size_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -40,3 +42,24 @@ pub fn convert_sytax_style(s_style: syntect::highlighting::Style) -> Style {
     ));
     style
 }
+
+/// The color entries of this kind are listed in
+pub fn file_color(kind: FileKind) -> Color {
+    match kind {
+        FileKind::Dir => Color::Cyan,
+        FileKind::File => Color::White,
+        FileKind::Symlink => Color::Green,
+        FileKind::Other => Color::Gray,
+    }
+}
+
+/// The color of the marker of a git status
+pub fn git_color(status: FileStatus) -> Color {
+    match status {
+        FileStatus::Ignored => Color::DarkGray,
+        FileStatus::Untracked => Color::LightRed,
+        FileStatus::Staged => Color::Green,
+        FileStatus::Modified => Color::Yellow,
+        FileStatus::Conflicted => Color::Red,
+    }
+}